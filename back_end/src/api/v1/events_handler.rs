@@ -1,35 +1,59 @@
-use crate::game::{Game, Result};
+use crate::game::{now_millis, CodeNamesError, Game, Result};
+use crate::game_notifier::{GameNotifier, GameUpdate};
+use crate::game_repo::GameStore;
 use crate::game_service::GameService;
 use common::api::v1::models::{
-    ClientMessage, Clue, EventRequest, GameEvent, GameView, Group, Guess, Player, ServerMessage,
-    Tile,
+    ClientMessage, Clue, ErrorResponse, EventRequest, Group, Guess, Player, ServerMessage,
 };
 use futures::stream::SplitSink;
 use futures::{SinkExt, StreamExt};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::Mutex;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
+use super::redaction::redact;
 use super::{game_id_query_param, player_id_cookie};
 
-pub struct EventsRouter {
+pub struct EventsRouter<S: GameStore, N: GameNotifier> {
     connections: Connections,
-    game_service: Arc<GameService>,
+    turn_timers: TurnTimers,
+    game_service: Arc<GameService<S, N>>,
 }
 
-impl EventsRouter {
-    pub fn new(game_service: Arc<GameService>) -> Self {
-        let connections = Default::default();
+impl<S: GameStore, N: GameNotifier> EventsRouter<S, N> {
+    pub fn new(game_service: Arc<GameService<S, N>>) -> Self {
+        let connections: Connections = Default::default();
+        let turn_timers: TurnTimers = Default::default();
+        tokio::spawn(
+            EventsHandler::new(
+                game_service.clone(),
+                connections.clone(),
+                turn_timers.clone(),
+            )
+            .handle_updates(game_service.subscribe_to_updates()),
+        );
+        tokio::spawn(
+            EventsHandler::new(
+                game_service.clone(),
+                connections.clone(),
+                turn_timers.clone(),
+            )
+            .sweep_expired_games(),
+        );
         Self {
             connections,
+            turn_timers,
             game_service,
         }
     }
 
     pub fn route(&self) -> BoxedFilter<(impl Reply,)> {
         let connections = self.connections.clone();
+        let turn_timers = self.turn_timers.clone();
         let game_service = self.game_service.clone();
         Filter::boxed(
             warp::path!("events")
@@ -39,6 +63,7 @@ impl EventsRouter {
                 .map(move |ws, player_id, game_id| {
                     handle_ws_request(
                         connections.clone(),
+                        turn_timers.clone(),
                         game_service.clone(),
                         ws,
                         player_id,
@@ -49,9 +74,10 @@ impl EventsRouter {
     }
 }
 
-fn handle_ws_request(
+fn handle_ws_request<S: GameStore, N: GameNotifier>(
     connections: Connections,
-    game_service: Arc<GameService>,
+    turn_timers: TurnTimers,
+    game_service: Arc<GameService<S, N>>,
     ws: warp::ws::Ws,
     player_id: String,
     game_id: String,
@@ -59,20 +85,26 @@ fn handle_ws_request(
     let player_id: String = player_id.clone();
     let game_id: String = game_id.clone();
     ws.on_upgrade(move |socket| {
-        EventsHandler::new(game_service.clone(), connections)
+        EventsHandler::new(game_service.clone(), connections, turn_timers)
             .handle_socket(socket, player_id, game_id)
     })
 }
 
-struct EventsHandler {
+struct EventsHandler<S: GameStore, N: GameNotifier> {
     connections: Connections,
-    game_service: Arc<GameService>,
+    turn_timers: TurnTimers,
+    game_service: Arc<GameService<S, N>>,
 }
 
-impl EventsHandler {
-    pub fn new(game_service: Arc<GameService>, connections: Connections) -> Self {
+impl<S: GameStore, N: GameNotifier> EventsHandler<S, N> {
+    pub fn new(
+        game_service: Arc<GameService<S, N>>,
+        connections: Connections,
+        turn_timers: TurnTimers,
+    ) -> Self {
         Self {
             connections,
+            turn_timers,
             game_service,
         }
     }
@@ -99,40 +131,157 @@ impl EventsHandler {
             match message_result {
                 Ok(message) => {
                     println!("Received message: {:?}", message);
-                    self.handle_message(message, game_id.as_str(), player_id.as_str())
-                        .await
+                    self.handle_message(
+                        message,
+                        game_id.as_str(),
+                        player_id.as_str(),
+                        connection_id.as_str(),
+                    )
+                    .await
                 }
                 Err(err) => eprintln!("Error receiving WebSocket message: {}", err),
             }
         }
 
         let mut connections = self.connections.lock().await;
-        let game_connections = connections.get_mut(game_id.as_str()).unwrap();
-        game_connections.remove(connection_id.as_str());
-        if game_connections.is_empty() {
-            // No more users are connected to this game, so drop this game's connections entry.
-            connections.remove(game_id.as_str());
+        // The game's connections entry will already be gone if the game has expired.
+        if let Some(game_connections) = connections.get_mut(game_id.as_str()) {
+            game_connections.remove(connection_id.as_str());
+            if game_connections.is_empty() {
+                // No more users are connected to this game, so drop this game's connections entry.
+                connections.remove(game_id.as_str());
+            }
+        }
+    }
+
+    /// Pushes the latest state of every changed game to this server's players of that game.
+    async fn handle_updates(self, mut updates: broadcast::Receiver<GameUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(GameUpdate::Changed(game_id)) => self.push_state_update(game_id.as_str()).await,
+                Ok(GameUpdate::All) => self.push_every_state_update().await,
+                Err(RecvError::Lagged(missed)) => {
+                    eprintln!("Missed {missed} game update notifications, refreshing every game");
+                    self.push_every_state_update().await
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    async fn push_every_state_update(&self) {
+        let game_ids: Vec<String> = self.connections.lock().await.keys().cloned().collect();
+        for game_id in game_ids {
+            self.push_state_update(game_id.as_str()).await
         }
     }
 
-    async fn handle_message(&self, message: warp::ws::Message, game_id: &str, player_id: &str) {
+    /// Periodically tells the players of any game that no longer exists that it has expired, and
+    /// disconnects them.
+    async fn sweep_expired_games(self) {
+        let mut interval = tokio::time::interval(EXPIRED_GAMES_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let game_ids: Vec<String> = self.connections.lock().await.keys().cloned().collect();
+            for game_id in game_ids {
+                if let Err(CodeNamesError::NoSuchGameError) =
+                    self.game_service.get_game(game_id.as_str()).await
+                {
+                    self.close_expired_game_connections(game_id.as_str()).await
+                }
+            }
+        }
+    }
+
+    async fn close_expired_game_connections(&self, game_id: &str) {
+        let game_connections = self.connections.lock().await.remove(game_id);
+        close_connections(
+            game_connections.into_iter().flat_map(HashMap::into_values),
+            &ServerMessage::GameExpired,
+        )
+        .await
+    }
+
+    async fn push_state_update(&self, game_id: &str) {
+        if !self.connections.lock().await.contains_key(game_id) {
+            // None of this game's players are connected to this server.
+            return;
+        }
+        match self.game_service.get_game(game_id).await {
+            Ok(game) => self.send_state_update(game_id, game).await,
+            // The game was removed after its last player left, so there is nobody to update.
+            Err(CodeNamesError::NoSuchGameError) => {}
+            Err(err) => eprintln!("Error retrieving game: {}", err),
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        message: warp::ws::Message,
+        game_id: &str,
+        player_id: &str,
+        connection_id: &str,
+    ) {
         if message.is_text() {
-            self.handle_text_message(message.to_str().unwrap(), game_id, player_id)
+            self.handle_text_message(message.to_str().unwrap(), game_id, player_id, connection_id)
                 .await
         } else {
             eprintln!("Unrecognised message: {:?}", message)
         }
     }
 
-    async fn handle_text_message(&self, text: &str, game_id: &str, player_id: &str) {
+    async fn handle_text_message(
+        &self,
+        text: &str,
+        game_id: &str,
+        player_id: &str,
+        connection_id: &str,
+    ) {
         match serde_json::from_str::<ClientMessage>(text) {
             Ok(message) => match message {
-                ClientMessage::EventRequest(event_req) => {
-                    match self.handle_request(event_req, game_id, player_id).await {
-                        Ok(response) => self.send_state_update(game_id, response).await,
-                        Err(err) => eprintln!("Error performing game event request: {err}"),
+                ClientMessage::EventRequest {
+                    request_id,
+                    request,
+                } => match self
+                    .handle_request(request.clone(), game_id, player_id)
+                    .await
+                {
+                    // The new state is pushed to every player once the change has been published,
+                    // but that can arrive after the acknowledgement, so the requesting connection
+                    // is sent its state update first.
+                    Ok(game) => {
+                        if let Some(view) = redact(&game, player_id) {
+                            self.send_to_connection(
+                                game_id,
+                                connection_id,
+                                &ServerMessage::StateUpdate(Box::new(view)),
+                            )
+                            .await
+                        }
+                        if let Some(request_id) = request_id {
+                            self.send_to_connection(
+                                game_id,
+                                connection_id,
+                                &ServerMessage::Ack { request_id },
+                            )
+                            .await
+                        }
                     }
-                }
+                    Err(err) => {
+                        eprintln!("Error performing game event request: {err}");
+                        self.send_to_connection(
+                            game_id,
+                            connection_id,
+                            &ServerMessage::Error(ErrorResponse {
+                                code: err.code(),
+                                message: err.to_string(),
+                                request,
+                                request_id,
+                            }),
+                        )
+                        .await
+                    }
+                },
                 ClientMessage::Heartbeat => {}
             },
             Err(err) => eprintln!("Error parsing WebSocket message to game event request: {err}"),
@@ -176,101 +325,169 @@ impl EventsHandler {
                     .guess(game_id, player_id, Guess { tile_index })
                     .await
             }
+            EventRequest::EndTurn => self.game_service.end_turn(game_id, player_id).await,
+            EventRequest::Forfeit => self.game_service.forfeit(game_id, player_id).await,
+            EventRequest::Pause => self.game_service.pause(game_id, player_id).await,
+            EventRequest::Resume => self.game_service.resume(game_id, player_id).await,
+            EventRequest::RequestSwap { new_group } => {
+                self.game_service
+                    .request_swap(game_id, player_id, new_group)
+                    .await
+            }
+            EventRequest::ApproveSwap { swapped_id } => {
+                self.game_service
+                    .approve_swap(game_id, player_id, swapped_id.as_str())
+                    .await
+            }
+            EventRequest::DenySwap { swapped_id } => {
+                self.game_service
+                    .deny_swap(game_id, player_id, swapped_id.as_str())
+                    .await
+            }
+            EventRequest::TransferHost { new_host_id } => {
+                self.game_service
+                    .transfer_host(game_id, player_id, new_host_id.as_str())
+                    .await
+            }
+            EventRequest::KickPlayer { kicked_id } => {
+                self.game_service
+                    .kick_player(game_id, player_id, kicked_id.as_str())
+                    .await
+            }
+            EventRequest::Rematch { rotate_spy_masters } => {
+                self.game_service
+                    .rematch(game_id, player_id, rotate_spy_masters)
+                    .await
+            }
+        }
+    }
+
+    /// Ends the game's turn once its deadline passes, unless a timer for that deadline is already
+    /// running. Every server with players in the game starts one, but only the first to fire ends
+    /// the turn, and the new state reaches everyone through the usual update notifications.
+    async fn schedule_turn_expiry(&self, game_id: &str, deadline: u64) {
+        let mut turn_timers = self.turn_timers.lock().await;
+        if turn_timers.get(game_id) == Some(&deadline) {
+            return;
         }
+        turn_timers.insert(game_id.to_string(), deadline);
+        drop(turn_timers);
+
+        let game_service = self.game_service.clone();
+        let turn_timers = self.turn_timers.clone();
+        let game_id = game_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(deadline.saturating_sub(now_millis()))).await;
+            match game_service.expire_turn(game_id.as_str()).await {
+                // The turn already ended in time or was expired by another server, the game was
+                // paused, or the game is gone.
+                Ok(_)
+                | Err(
+                    CodeNamesError::DeadlineNotReachedError
+                    | CodeNamesError::GamePausedError
+                    | CodeNamesError::NoSuchGameError,
+                ) => {}
+                Err(err) => eprintln!("Failed to expire turn of game {}: {}", game_id, err),
+            }
+            let mut turn_timers = turn_timers.lock().await;
+            if turn_timers.get(game_id.as_str()) == Some(&deadline) {
+                turn_timers.remove(game_id.as_str());
+            }
+        });
     }
 
     async fn send_state_update(&self, game_id: &str, game: Game) {
-        match self.connections.lock().await.get_mut(game_id) {
-            Some(conns) => {
-                for (_connection_id, (player_id, sink)) in conns {
-                    let is_spymaster = game.teams.red.spy_masters.contains_key(player_id)
-                        || game.teams.blue.spy_masters.contains_key(player_id);
-                    let json = if is_spymaster {
-                        serde_json::to_string(&ServerMessage::StateUpdate(GameView::from_game(
-                            game.clone(),
-                            true,
-                            player_id.as_str(),
-                        )))
-                    } else {
-                        serde_json::to_string(&ServerMessage::StateUpdate(GameView::from_game(
-                            game.clone(),
-                            false,
-                            player_id.as_str(),
-                        )))
-                    }
+        // A paused game's deadline is pushed back when it resumes, and is rescheduled then.
+        if let (Some(deadline), None) = (game.deadline, game.paused_at) {
+            self.schedule_turn_expiry(game_id, deadline).await;
+        }
+        // If every player of this game has disconnected from this server in the meantime, there
+        // is nobody to send to.
+        if let Some(conns) = self.connections.lock().await.get_mut(game_id) {
+            let kicked_connection_ids: Vec<String> = conns
+                .iter()
+                .filter(|(_, (player_id, _))| game.kicked_ids.contains(player_id))
+                .map(|(connection_id, _)| connection_id.clone())
+                .collect();
+            let kicked_connections: Vec<_> = kicked_connection_ids
+                .iter()
+                .filter_map(|connection_id| conns.remove(connection_id))
+                .collect();
+            close_connections(kicked_connections, &ServerMessage::Kicked).await;
+
+            for (player_id, sink) in conns.values_mut() {
+                let Some(view) = redact(&game, player_id) else {
+                    // The player has left the game, but not yet closed their connection.
+                    continue;
+                };
+                let json = serde_json::to_string(&ServerMessage::StateUpdate(Box::new(view)))
                     .expect("Failed to serialize game state update");
-                    let json = json.as_str();
-                    println!("Sending {:?} to player {}", json, player_id);
-                    match sink.send(warp::ws::Message::text(json)).await {
-                        Ok(_) => println!(
-                            "Successfully sent state update {} to player {}",
-                            json, player_id
-                        ),
-                        Err(err) => {
-                            eprintln!("Failed to send state update {}: {}", json, err)
-                        }
+                let json = json.as_str();
+                println!("Sending {:?} to player {}", json, player_id);
+                match sink.send(warp::ws::Message::text(json)).await {
+                    Ok(_) => println!(
+                        "Successfully sent state update {} to player {}",
+                        json, player_id
+                    ),
+                    Err(err) => {
+                        eprintln!("Failed to send state update {}: {}", json, err)
                     }
                 }
             }
-            None => {
-                panic!("No connections found for game {game_id}")
-            }
         }
     }
-}
 
-trait FromGame {
-    fn from_game(game: Game, is_spymaster: bool, player_id: &str) -> Self;
+    /// Sends a message to the single connection that made a request, rather than the whole game.
+    async fn send_to_connection(
+        &self,
+        game_id: &str,
+        connection_id: &str,
+        message: &ServerMessage,
+    ) {
+        let json = serde_json::to_string(message).expect("Failed to serialize server message");
+        match self
+            .connections
+            .lock()
+            .await
+            .get_mut(game_id)
+            .and_then(|conns| conns.get_mut(connection_id))
+        {
+            Some((player_id, sink)) => match sink.send(warp::ws::Message::text(&json)).await {
+                Ok(_) => println!("Successfully sent {} to player {}", json, player_id),
+                Err(err) => eprintln!("Failed to send {}: {}", json, err),
+            },
+            None => eprintln!("No connection {connection_id} found for game {game_id}"),
+        }
+    }
 }
 
-impl FromGame for GameView {
-    fn from_game(game: Game, is_spymaster: bool, player_id: &str) -> Self {
-        // Collect the indexes of every tile that has already been guessed.
-        let guessed_tile_indexes: Vec<u8> = game
-            .history
-            .iter()
-            .filter_map(|event| match event {
-                GameEvent::Clue(_) => None,
-                GameEvent::Guess(guess) => Some(guess.tile_index),
-            })
-            .collect();
-        let mut tile_index: u8 = 0;
-        let tiles = game.tiles.map(|tile| {
-            let tile = Tile {
-                word: tile.word,
-                colour: if is_spymaster || guessed_tile_indexes.contains(&tile_index) {
-                    Some(tile.colour)
-                } else {
-                    None
-                },
-            };
-            tile_index += 1;
-            tile
-        });
-
-        let this_player = game
-            .teams
-            .spectators
-            .get(player_id)
-            .or_else(|| game.teams.blue.guessers.get(player_id))
-            .or_else(|| game.teams.blue.spy_masters.get(player_id))
-            .or_else(|| game.teams.red.guessers.get(player_id))
-            .or_else(|| game.teams.red.spy_masters.get(player_id))
-            .expect("Player not found")
-            .clone();
-
-        Self {
-            is_started: game.is_started,
-            tiles,
-            teams: game.teams,
-            this_player,
-            team_turn: game.team_turn,
-            next_action: game.next_action,
-            history: game.history,
+/// Sends a final message to each of the given connections, then closes them.
+async fn close_connections(
+    connections: impl IntoIterator<Item = (String, WebSocketSink)>,
+    message: &ServerMessage,
+) {
+    let json = serde_json::to_string(message).expect("Failed to serialize server message");
+    for (player_id, mut sink) in connections {
+        if let Err(err) = sink.send(warp::ws::Message::text(&json)).await {
+            eprintln!("Failed to send {} to player {}: {}", json, player_id, err)
+        }
+        if let Err(err) = sink.close().await {
+            eprintln!(
+                "Failed to close connection of player {}: {}",
+                player_id, err
+            )
         }
     }
 }
 
+/// How often to check whether the games that players are connected to have expired.
+const EXPIRED_GAMES_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 type Connections = Arc<Mutex<HashMap<String, GameConnections>>>;
 
-type GameConnections = HashMap<String, (String, SplitSink<warp::ws::WebSocket, warp::ws::Message>)>;
+/// The deadline that each game's turn timer is running for, by game ID.
+type TurnTimers = Arc<Mutex<HashMap<String, u64>>>;
+
+type GameConnections = HashMap<String, (String, WebSocketSink)>;
+
+type WebSocketSink = SplitSink<warp::ws::WebSocket, warp::ws::Message>;
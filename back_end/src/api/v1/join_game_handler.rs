@@ -1,10 +1,15 @@
 use crate::api::v1::player_id_cookie;
+use crate::game::CodeNamesError;
+use crate::game_notifier::GameNotifier;
+use crate::game_repo::GameStore;
 use crate::game_service::GameService;
-use common::api::v1::models::{JoinGameRequest, Player, Group};
+use common::api::v1::models::{Group, JoinGameRequest, Player};
 use std::sync::Arc;
-use warp::Filter;
+use warp::{Filter, Reply};
 
-pub fn route(game_service: Arc<GameService>) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+pub fn route<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
+) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
     Filter::boxed(
         warp::post()
             .and(warp::path!("join-game"))
@@ -16,12 +21,12 @@ pub fn route(game_service: Arc<GameService>) -> warp::filters::BoxedFilter<(impl
     )
 }
 
-async fn handle_request(
-    game_service: Arc<GameService>,
+async fn handle_request<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
     player_id: String,
     request: JoinGameRequest,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    game_service
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match game_service
         .add_player(
             request.game_id.as_str(),
             player_id.as_str(),
@@ -32,6 +37,13 @@ async fn handle_request(
             },
         )
         .await
-        .map_err(|err| warp::reject::custom(err))?;
-    Ok(warp::http::StatusCode::OK)
+    {
+        Ok(_) => Ok(warp::http::StatusCode::OK.into_response()),
+        // The host has kicked this player out of the game.
+        Err(err @ CodeNamesError::PlayerKickedError) => Ok(warp::http::Response::builder()
+            .status(warp::http::StatusCode::FORBIDDEN)
+            .body(err.to_string().into())
+            .unwrap()),
+        Err(err) => Err(warp::reject::custom(err)),
+    }
 }
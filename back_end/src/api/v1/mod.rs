@@ -1,4 +1,6 @@
 use crate::game::CodeNamesError;
+use crate::game_notifier::GameNotifier;
+use crate::game_repo::GameStore;
 use crate::game_service::GameService;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -12,13 +14,18 @@ pub mod events_handler;
 pub mod join_game_handler;
 pub mod new_game_handler;
 pub mod player_joined_handler;
+pub mod redaction;
+pub mod word_packs_handler;
 
-pub fn routes(game_service: Arc<GameService>) -> BoxedFilter<(impl Reply,)> {
+pub fn routes<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
+) -> BoxedFilter<(impl Reply,)> {
     warp::path!("play" / "v1" / ..)
         .and(
             new_game_handler::route(game_service.clone())
                 .or(join_game_handler::route(game_service.clone()))
                 .or(player_joined_handler::route(game_service.clone()))
+                .or(word_packs_handler::route(game_service.clone()))
                 .or(EventsRouter::new(game_service.clone()).route())
                 .recover(handle_missing_query_param_rejection)
                 .boxed()
@@ -110,3 +117,6 @@ where
         })
         .boxed()
 }
+
+#[cfg(test)]
+mod tests;
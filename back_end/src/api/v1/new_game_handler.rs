@@ -1,11 +1,16 @@
 use crate::api::v1::player_id_cookie;
+use crate::game::CodeNamesError;
+use crate::game_notifier::GameNotifier;
+use crate::game_repo::GameStore;
 use crate::game_service::GameService;
-use common::api::v1::models::{NewGameRequest, NewGameResponse, Player, Group};
+use common::api::v1::models::{Group, NewGameRequest, NewGameResponse, Player};
 use std::sync::Arc;
-use warp::reply::json;
+use warp::reply::{json, Reply};
 use warp::Filter;
 
-pub fn route(game_service: Arc<GameService>) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+pub fn route<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
+) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
     Filter::boxed(
         warp::post()
             .and(warp::path!("new-game"))
@@ -17,12 +22,36 @@ pub fn route(game_service: Arc<GameService>) -> warp::filters::BoxedFilter<(impl
     )
 }
 
-async fn handle_request(
-    game_service: Arc<GameService>,
+async fn handle_request<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
     player_id: String,
     request: NewGameRequest,
-) -> Result<warp::reply::Json, warp::Rejection> {
-    let game_id: String = game_service.new_game(player_id.clone()).await;
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let game_id: String = match game_service
+        .new_game(
+            player_id.clone(),
+            request.word_list,
+            request.seed,
+            request.settings,
+        )
+        .await
+    {
+        Ok(game_id) => game_id,
+        // The host chose a word list that cannot be used.
+        Err(
+            err @ (CodeNamesError::DuplicateWordError { .. }
+            | CodeNamesError::InvalidWordError { .. }
+            | CodeNamesError::NoSuchWordListError { .. }
+            | CodeNamesError::NotEnoughWordsError { .. }
+            | CodeNamesError::TooManyWordsError { .. }),
+        ) => {
+            return Ok(warp::http::Response::builder()
+                .status(warp::http::StatusCode::BAD_REQUEST)
+                .body(err.to_string().into())
+                .unwrap())
+        }
+        Err(err) => return Err(warp::reject::custom(err)),
+    };
     game_service
         .add_player(
             game_id.as_str(),
@@ -34,6 +63,6 @@ async fn handle_request(
             },
         )
         .await
-        .map_err(|err| warp::reject::custom(err))?;
-    Ok(json(&NewGameResponse { game_id }))
+        .map_err(warp::reject::custom)?;
+    Ok(json(&NewGameResponse { game_id }).into_response())
 }
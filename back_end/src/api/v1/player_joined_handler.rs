@@ -1,5 +1,7 @@
 use crate::api::v1::player_id_cookie;
 use crate::game::CodeNamesError;
+use crate::game_notifier::GameNotifier;
+use crate::game_repo::GameStore;
 use crate::game_service::GameService;
 use common::api::v1::models::PlayerJoinedResponse;
 use std::sync::Arc;
@@ -8,7 +10,9 @@ use warp::Filter;
 
 use super::game_id_query_param;
 
-pub fn route(game_service: Arc<GameService>) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+pub fn route<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
+) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
     Filter::boxed(
         warp::get()
             .and(warp::path!("player-joined"))
@@ -20,8 +24,8 @@ pub fn route(game_service: Arc<GameService>) -> warp::filters::BoxedFilter<(impl
     )
 }
 
-async fn handle_request(
-    game_service: Arc<GameService>,
+async fn handle_request<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
     player_id: String,
     game_id: String,
 ) -> warp::reply::Response {
@@ -29,12 +33,18 @@ async fn handle_request(
         .player_exists(game_id.as_str(), player_id.as_str())
         .await
     {
-        Ok(already_joined) => warp::reply::json(&PlayerJoinedResponse { already_joined }).into_response(),
+        Ok(already_joined) => {
+            warp::reply::json(&PlayerJoinedResponse { already_joined }).into_response()
+        }
         Err(err) => match err {
             CodeNamesError::NoSuchGameError => warp::http::Response::builder()
                 .status(warp::http::StatusCode::NOT_FOUND)
                 .body(format!("No game with ID {} found", game_id).into())
                 .unwrap(),
+            CodeNamesError::StorageError(_) => warp::http::Response::builder()
+                .status(warp::http::StatusCode::INTERNAL_SERVER_ERROR)
+                .body(err.to_string().into())
+                .unwrap(),
             _ => {
                 panic!("These errors should not appear on a simple player existence check")
             }
@@ -0,0 +1,161 @@
+use crate::game::Game;
+use common::api::v1::models::{GameView, Role, Tile};
+
+/// Builds the view of a game that a player is allowed to see, based on their role. This is the
+/// only place that decides what leaves the server, so every `GameView` must be built through it.
+///
+/// Spymasters see the colour of every tile, while guessers and spectators only see the colours of
+/// the tiles that have been revealed, until the game is over. Nobody sees another player's ID or
+/// the host's ID, as the teams are keyed by public IDs.
+///
+/// Returns `None` if the player is not in the game.
+pub fn redact(game: &Game, player_id: &str) -> Option<GameView> {
+    let this_player = game.player(player_id)?.clone();
+    let this_player_id = game.public_id(player_id).ok()?.to_string();
+    let role = this_player.group.role();
+
+    let revealed_tiles = game.revealed_tiles();
+    // Once the game is over, the whole board is revealed to everyone.
+    let is_over = game.is_over();
+    let mut tile_index: u8 = 0;
+    let tiles = game.tiles.clone().map(|tile| {
+        let is_visible = role == Role::SpyMaster || is_over || revealed_tiles.contains(&tile_index);
+        tile_index += 1;
+        Tile {
+            word: tile.word,
+            colour: is_visible.then_some(tile.colour),
+        }
+    });
+
+    Some(GameView {
+        is_started: game.is_started,
+        tiles,
+        language: game.language.clone(),
+        seed: (game.host_id == player_id).then_some(game.seed),
+        settings: game.settings.clone(),
+        deadline: game.deadline,
+        paused_at: game.paused_at,
+        teams: game.teams.clone(),
+        this_player,
+        this_player_id,
+        team_turn: game.team_turn.clone(),
+        next_action: game.next_action.clone(),
+        // Events only refer to tiles by index, so their colours can be seen on the revealed tiles.
+        history: game.history.clone(),
+        result: game.result.clone(),
+        series: game.series.clone(),
+        pending_swaps: game.pending_swaps.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::redaction::*;
+    use crate::game::test_fixtures::*;
+    use crate::game::Result;
+    use common::api::v1::models::{GameOverReason, GameResult, Guess, ServerMessage, TeamColour};
+
+    #[test]
+    fn when_guesser_views_game_then_only_revealed_colours_are_sent() -> Result<()> {
+        let game = game_with_guess()?;
+
+        for player_id in [RED_GUESSER, SPECTATOR] {
+            let view = state_update(state_update_json(&game, player_id).as_str());
+            for (tile_index, tile) in view.tiles.iter().enumerate() {
+                if tile_index == REVEALED_TILE {
+                    assert_eq!(tile.colour, Some(game.tiles[tile_index].colour.clone()));
+                } else {
+                    assert_eq!(tile.colour, None, "tile {} was revealed", tile_index);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_guesser_views_game_then_no_secrets_are_sent() -> Result<()> {
+        let game = game_with_guess()?;
+
+        for player_id in [RED_GUESSER, SPECTATOR] {
+            let json = state_update_json(&game, player_id);
+
+            for (other_player_id, _) in PLAYERS {
+                assert!(!json.contains(format!("\"{}\"", other_player_id).as_str()));
+                assert!(json.contains(game.public_id(other_player_id)?));
+            }
+            assert_eq!(state_update(json.as_str()).seed, None);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_views_game_then_the_seed_is_sent() -> Result<()> {
+        let game = game_with_guess()?;
+
+        let view = state_update(state_update_json(&game, BLUE_SPY_MASTER).as_str());
+
+        assert_eq!(view.seed, Some(game.seed));
+        Ok(())
+    }
+
+    #[test]
+    fn when_spymaster_views_game_then_all_colours_are_sent() -> Result<()> {
+        let game = game_with_guess()?;
+
+        let view = state_update(state_update_json(&game, RED_SPY_MASTER).as_str());
+
+        assert!(view.tiles.iter().all(|tile| tile.colour.is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn when_game_is_over_then_all_colours_are_sent_to_everyone() -> Result<()> {
+        let mut game = game_with_guess()?;
+        game.result = Some(GameResult {
+            winner: TeamColour::Blue,
+            reason: GameOverReason::Forfeit,
+        });
+
+        let view = state_update(state_update_json(&game, SPECTATOR).as_str());
+
+        assert!(view.tiles.iter().all(|tile| tile.colour.is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn when_player_not_in_game_then_there_is_no_view() -> Result<()> {
+        let game = game_with_guess()?;
+
+        assert!(redact(&game, "not_a_player").is_none());
+        Ok(())
+    }
+
+    const REVEALED_TILE: usize = 1;
+
+    /// A started game with a spectator, where the red team have found one of their agents.
+    fn game_with_guess() -> Result<Game> {
+        let mut game = started_game();
+        game.add_player(SPECTATOR, player(SPECTATOR))?;
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        game.guess(
+            RED_GUESSER,
+            Guess {
+                tile_index: REVEALED_TILE as u8,
+            },
+            NOW,
+        )?;
+        Ok(game)
+    }
+
+    fn state_update_json(game: &Game, player_id: &str) -> String {
+        let view = redact(game, player_id).unwrap();
+        serde_json::to_string(&ServerMessage::StateUpdate(Box::new(view))).unwrap()
+    }
+
+    fn state_update(json: &str) -> GameView {
+        match serde_json::from_str(json).unwrap() {
+            ServerMessage::StateUpdate(view) => *view,
+            message => panic!("Expected a state update, got {:?}", message),
+        }
+    }
+}
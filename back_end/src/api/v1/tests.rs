@@ -0,0 +1,617 @@
+use crate::api::v1::*;
+use crate::game::now_millis;
+use crate::game_notifier::in_process_notifier::InProcessGameNotifier;
+use crate::game_repo::in_memory_repo::InMemoryGameRepository;
+use crate::word_lists::WordLists;
+use common::api::v1::models::{
+    Action, ClientMessage, ClueCount, ErrorCode, EventRequest, GameEvent, GameSettings, GameView,
+    Group, JoinGameRequest, NewGameRequest, NewGameResponse, PlayerJoinedResponse, RequestId,
+    ServerMessage, TeamColour, TileColour, Timeout, WordList, WordPack,
+};
+use std::num::NonZeroU32;
+use std::time::Duration;
+use warp::http::StatusCode;
+use warp::test::WsClient;
+
+#[tokio::test]
+async fn when_request_has_no_player_id_cookie_then_one_is_set() {
+    let filter = warp::any().map(warp::reply).boxed();
+    let filter = player_id_cookie_wrap(filter);
+
+    let response = warp::test::request().reply(&filter).await;
+
+    let cookie = response
+        .headers()
+        .get(warp::http::header::SET_COOKIE)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(cookie.starts_with(&format!("{}=", PLAYER_ID_COOKIE_NAME)));
+    assert!(cookie.contains("HttpOnly"));
+
+    let response = warp::test::request()
+        .header("cookie", cookie_header(HOST))
+        .reply(&filter)
+        .await;
+
+    assert!(response
+        .headers()
+        .get(warp::http::header::SET_COOKIE)
+        .is_none());
+}
+
+#[tokio::test]
+async fn when_players_join_then_player_joined_reports_them() {
+    let routes = test_routes();
+
+    let game_id = new_game(&routes, HOST).await;
+
+    assert!(player_joined(&routes, HOST, game_id.as_str()).await);
+    assert!(!player_joined(&routes, BLUE_GUESSER, game_id.as_str()).await);
+    join_game(&routes, BLUE_GUESSER, game_id.as_str()).await;
+    assert!(player_joined(&routes, BLUE_GUESSER, game_id.as_str()).await);
+}
+
+#[tokio::test]
+async fn when_new_game_has_too_few_custom_words_then_request_is_bad() {
+    let routes = test_routes();
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/play/v1/new-game")
+        .header("cookie", cookie_header(HOST))
+        .json(&NewGameRequest {
+            player_name: "host_name".to_string(),
+            word_list: Some(WordList::Custom(vec!["word".to_string()])),
+            seed: None,
+            settings: Default::default(),
+        })
+        .reply(&routes)
+        .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn when_game_uses_a_word_pack_then_players_see_its_language() {
+    let routes = test_routes();
+
+    let response = warp::test::request()
+        .path("/play/v1/word-packs")
+        .header("cookie", cookie_header(HOST))
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let packs: Vec<WordPack> = serde_json::from_slice(response.body()).unwrap();
+    let pack = packs.iter().find(|pack| pack.name == "deu").unwrap();
+    assert_eq!(pack.language.as_deref(), Some("de"));
+
+    let game_id = new_game_with_request(
+        &routes,
+        HOST,
+        NewGameRequest {
+            player_name: "host_name".to_string(),
+            word_list: Some(WordList::Named(pack.name.clone())),
+            seed: None,
+            settings: Default::default(),
+        },
+    )
+    .await;
+    let mut client = connect(&routes, HOST, game_id.as_str()).await;
+
+    let view = wait_for_state(&mut client, |_| true).await;
+    assert_eq!(view.language.as_deref(), Some("de"));
+}
+
+#[tokio::test]
+async fn when_game_is_seeded_then_only_the_host_sees_the_seed() {
+    let routes = test_routes();
+    let game_id = new_game_with_request(
+        &routes,
+        HOST,
+        NewGameRequest {
+            player_name: "host_name".to_string(),
+            word_list: None,
+            seed: Some(42),
+            settings: Default::default(),
+        },
+    )
+    .await;
+    join_game(&routes, BLUE_GUESSER, game_id.as_str()).await;
+
+    let mut host = connect(&routes, HOST, game_id.as_str()).await;
+    let mut guesser = connect(&routes, BLUE_GUESSER, game_id.as_str()).await;
+
+    assert_eq!(wait_for_state(&mut host, |_| true).await.seed, Some(42));
+    assert_eq!(wait_for_state(&mut guesser, |_| true).await.seed, None);
+}
+
+#[tokio::test]
+async fn when_spy_master_runs_out_of_time_then_everyone_sees_the_turn_pass() {
+    let routes = test_routes();
+    let settings = GameSettings {
+        clue_time_limit_secs: NonZeroU32::new(1),
+        ..Default::default()
+    };
+    let (_, mut clients) = started_game_with_settings(&routes, settings).await;
+
+    let view = wait_for_state(&mut clients[1], |view| view.is_started).await;
+    let first_turn = view.team_turn.clone();
+    assert!(view.deadline.is_some());
+
+    for client in clients.iter_mut() {
+        let view = wait_for_state(client, |view| view.team_turn != first_turn).await;
+        assert!(matches!(
+            view.history.as_slice(),
+            [GameEvent::Timeout(Timeout {
+                action: Action::Clue,
+                ..
+            })]
+        ));
+        assert_eq!(view.next_action, Action::Clue);
+    }
+}
+
+#[tokio::test]
+async fn when_host_pauses_then_turn_does_not_time_out_and_clues_are_rejected() {
+    let routes = test_routes();
+    let settings = GameSettings {
+        clue_time_limit_secs: NonZeroU32::new(2),
+        ..Default::default()
+    };
+    let (_, mut clients) = started_game_with_settings(&routes, settings).await;
+    let view = wait_for_state(&mut clients[0], |view| view.is_started).await;
+    let deadline = view.deadline.unwrap();
+    let (spy_master, _) = team_players(&view.team_turn);
+
+    send_request(&mut clients[0], 1, EventRequest::Pause).await;
+    wait_for_state(&mut clients[spy_master], |view| view.paused_at.is_some()).await;
+    send_request(
+        &mut clients[spy_master],
+        2,
+        EventRequest::Clue {
+            word: "clue".to_string(),
+            count: ClueCount::Number(1),
+        },
+    )
+    .await;
+    assert_eq!(
+        wait_for_error(&mut clients[spy_master]).await.code,
+        ErrorCode::GamePaused
+    );
+
+    tokio::time::sleep(Duration::from_millis(
+        deadline.saturating_sub(now_millis()) + 500,
+    ))
+    .await;
+    send_request(&mut clients[0], 3, EventRequest::Resume).await;
+    let view = wait_for_state(&mut clients[spy_master], |view| view.paused_at.is_none()).await;
+    assert!(matches!(
+        view.history.as_slice(),
+        [GameEvent::Pause(_), GameEvent::Resume(_)]
+    ));
+    assert!(view.deadline.unwrap() > deadline);
+}
+
+#[tokio::test]
+async fn when_game_does_not_exist_then_player_joined_is_not_found() {
+    let routes = test_routes();
+
+    let response = warp::test::request()
+        .path("/play/v1/player-joined?game-id=no-such-game")
+        .header("cookie", cookie_header(HOST))
+        .reply(&routes)
+        .await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn when_game_id_is_missing_then_request_is_bad() {
+    let routes = test_routes();
+
+    let response = warp::test::request()
+        .path("/play/v1/player-joined")
+        .header("cookie", cookie_header(HOST))
+        .reply(&routes)
+        .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn when_player_acts_out_of_turn_then_only_they_get_an_error() {
+    let routes = test_routes();
+    let (game_id, mut clients) = started_game(&routes).await;
+    let view = wait_for_state(&mut clients[0], |view| view.is_started).await;
+    let (waiting_spy_master, _) = team_players(&view.team_turn.other());
+
+    send_request(
+        &mut clients[waiting_spy_master],
+        7,
+        EventRequest::Clue {
+            word: "clue".to_string(),
+            count: ClueCount::Number(1),
+        },
+    )
+    .await;
+
+    let error = wait_for_error(&mut clients[waiting_spy_master]).await;
+    assert_eq!(error.code, ErrorCode::IllegalPlayerGroup);
+    assert_eq!(error.request_id, Some(7));
+    assert!(player_joined(&routes, PLAYERS[waiting_spy_master].0, game_id.as_str()).await);
+}
+
+#[tokio::test]
+async fn when_request_succeeds_then_its_state_update_arrives_before_the_ack() {
+    let routes = test_routes();
+    let game_id = new_game(&routes, HOST).await;
+    let mut client = connect(&routes, HOST, game_id.as_str()).await;
+    wait_for_state(&mut client, |_| true).await;
+
+    send_request(
+        &mut client,
+        3,
+        EventRequest::MovePlayer {
+            new_group: Group::RedGuessers,
+        },
+    )
+    .await;
+
+    loop {
+        match recv_message(&mut client).await {
+            ServerMessage::StateUpdate(view) if view.this_player.group == Group::RedGuessers => {
+                break
+            }
+            ServerMessage::Ack { .. } => panic!("Acknowledged before the state update was sent"),
+            _ => {}
+        }
+    }
+    assert!(matches!(
+        recv_message(&mut client).await,
+        ServerMessage::Ack { request_id: 3 }
+    ));
+}
+
+#[tokio::test]
+async fn when_host_kicks_player_then_they_are_disconnected_and_cannot_rejoin() {
+    let routes = test_routes();
+    let game_id = new_game(&routes, HOST).await;
+    join_game(&routes, BLUE_GUESSER, game_id.as_str()).await;
+    let mut host = connect(&routes, HOST, game_id.as_str()).await;
+    let mut kicked = connect(&routes, BLUE_GUESSER, game_id.as_str()).await;
+    wait_for_state(&mut host, |_| true).await;
+    let kicked_id = wait_for_state(&mut kicked, |_| true).await.this_player_id;
+
+    send_request(&mut host, 1, EventRequest::KickPlayer { kicked_id }).await;
+
+    loop {
+        if let ServerMessage::Kicked = recv_message(&mut kicked).await {
+            break;
+        }
+    }
+    kicked.recv_closed().await.unwrap();
+    let response = warp::test::request()
+        .method("POST")
+        .path("/play/v1/join-game")
+        .header("cookie", cookie_header(BLUE_GUESSER))
+        .json(&JoinGameRequest {
+            game_id: game_id.clone(),
+            player_name: format!("{}_name", BLUE_GUESSER),
+        })
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let mut kicked = connect(&routes, BLUE_GUESSER, game_id.as_str()).await;
+    assert!(matches!(
+        recv_message(&mut kicked).await,
+        ServerMessage::Kicked
+    ));
+}
+
+#[tokio::test]
+async fn when_full_game_is_played_then_each_role_sees_its_own_view() {
+    let routes = test_routes();
+    let (_, mut clients) = started_game(&routes).await;
+
+    let mut team_turn = None;
+    for (index, client) in clients.iter_mut().enumerate() {
+        let view = wait_for_state(client, |view| view.is_started).await;
+        team_turn = Some(view.team_turn.clone());
+        assert_eq!(view.this_player.group, PLAYERS[index].1);
+        let visible_colours = view
+            .tiles
+            .iter()
+            .filter(|tile| tile.colour.is_some())
+            .count();
+        match view.this_player.group {
+            Group::BlueSpyMasters | Group::RedSpyMasters => assert_eq!(visible_colours, 25),
+            _ => assert_eq!(visible_colours, 0),
+        }
+    }
+
+    // The team whose turn it is finds one of their agents, then ends their turn.
+    let team = team_turn.unwrap();
+    let (spy_master, guesser) = team_players(&team);
+    send_request(
+        &mut clients[spy_master],
+        1,
+        EventRequest::Clue {
+            word: "clue".to_string(),
+            count: ClueCount::Number(1),
+        },
+    )
+    .await;
+    wait_for_state(&mut clients[guesser], |view| {
+        view.next_action == Action::Guess
+    })
+    .await;
+    let board = wait_for_state(&mut clients[spy_master], |view| {
+        view.next_action == Action::Guess
+    })
+    .await
+    .tiles;
+    let agent = tile_of_colour(&board, &team_tile_colour(&team));
+    send_request(
+        &mut clients[guesser],
+        2,
+        EventRequest::Guess { tile_index: agent },
+    )
+    .await;
+    let view = wait_for_state(&mut clients[guesser], |view| {
+        view.tiles[agent as usize].colour.is_some()
+    })
+    .await;
+    for (tile_index, tile) in view.tiles.iter().enumerate() {
+        assert_eq!(tile.colour.is_some(), tile_index == agent as usize);
+    }
+    send_request(&mut clients[guesser], 3, EventRequest::EndTurn).await;
+    wait_for_state(&mut clients[spy_master], |view| view.team_turn != team).await;
+
+    // The other team then guesses the assassin, which ends the game.
+    let (other_spy_master, other_guesser) = team_players(&team.other());
+    send_request(
+        &mut clients[other_spy_master],
+        4,
+        EventRequest::Clue {
+            word: "clue".to_string(),
+            count: ClueCount::Number(1),
+        },
+    )
+    .await;
+    wait_for_state(&mut clients[other_guesser], |view| {
+        view.next_action == Action::Guess
+    })
+    .await;
+    let assassin = tile_of_colour(&board, &TileColour::Black);
+    send_request(
+        &mut clients[other_guesser],
+        5,
+        EventRequest::Guess {
+            tile_index: assassin,
+        },
+    )
+    .await;
+
+    for client in clients.iter_mut() {
+        let view = wait_for_state(client, |view| view.result.is_some()).await;
+        assert_eq!(view.result.unwrap().winner, team);
+        assert!(view.tiles.iter().all(|tile| tile.colour.is_some()));
+    }
+}
+
+const HOST: &str = "host";
+const BLUE_GUESSER: &str = "blue_guesser";
+const PLAYERS: [(&str, Group); 4] = [
+    (HOST, Group::BlueSpyMasters),
+    (BLUE_GUESSER, Group::BlueGuessers),
+    ("red_spy_master", Group::RedSpyMasters),
+    ("red_guesser", Group::RedGuessers),
+];
+
+/// How long to wait for a message before failing the test.
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn test_routes() -> BoxedFilter<(impl Reply,)> {
+    routes(Arc::new(GameService::new(
+        InMemoryGameRepository::default(),
+        InProcessGameNotifier::default(),
+        WordLists::bundled(),
+    )))
+}
+
+fn cookie_header(player_id: &str) -> String {
+    format!("{}={}", PLAYER_ID_COOKIE_NAME, player_id)
+}
+
+/// The indexes in `PLAYERS` of the spymaster and guesser of the given team.
+fn team_players(team: &TeamColour) -> (usize, usize) {
+    match team {
+        TeamColour::Blue => (0, 1),
+        TeamColour::Red => (2, 3),
+    }
+}
+
+fn team_tile_colour(team: &TeamColour) -> TileColour {
+    match team {
+        TeamColour::Blue => TileColour::Blue,
+        TeamColour::Red => TileColour::Red,
+    }
+}
+
+fn tile_of_colour(tiles: &[common::api::v1::models::Tile], colour: &TileColour) -> u8 {
+    tiles
+        .iter()
+        .position(|tile| tile.colour.as_ref() == Some(colour))
+        .unwrap() as u8
+}
+
+async fn new_game<F>(routes: &F, player_id: &str) -> String
+where
+    F: Filter + 'static,
+    F::Extract: Reply + Send,
+{
+    new_game_with_request(
+        routes,
+        player_id,
+        NewGameRequest {
+            player_name: format!("{}_name", player_id),
+            word_list: None,
+            seed: None,
+            settings: Default::default(),
+        },
+    )
+    .await
+}
+
+async fn new_game_with_request<F>(routes: &F, player_id: &str, request: NewGameRequest) -> String
+where
+    F: Filter + 'static,
+    F::Extract: Reply + Send,
+{
+    let response = warp::test::request()
+        .method("POST")
+        .path("/play/v1/new-game")
+        .header("cookie", cookie_header(player_id))
+        .json(&request)
+        .reply(routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    serde_json::from_slice::<NewGameResponse>(response.body())
+        .unwrap()
+        .game_id
+}
+
+async fn join_game<F>(routes: &F, player_id: &str, game_id: &str)
+where
+    F: Filter + 'static,
+    F::Extract: Reply + Send,
+{
+    let response = warp::test::request()
+        .method("POST")
+        .path("/play/v1/join-game")
+        .header("cookie", cookie_header(player_id))
+        .json(&JoinGameRequest {
+            game_id: game_id.to_string(),
+            player_name: format!("{}_name", player_id),
+        })
+        .reply(routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn player_joined<F>(routes: &F, player_id: &str, game_id: &str) -> bool
+where
+    F: Filter + 'static,
+    F::Extract: Reply + Send,
+{
+    let response = warp::test::request()
+        .path(format!("/play/v1/player-joined?game-id={}", game_id).as_str())
+        .header("cookie", cookie_header(player_id))
+        .reply(routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    serde_json::from_slice::<PlayerJoinedResponse>(response.body())
+        .unwrap()
+        .already_joined
+}
+
+async fn connect<F>(routes: &F, player_id: &str, game_id: &str) -> WsClient
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply + Send,
+{
+    warp::test::ws()
+        .path(format!("/play/v1/events?game-id={}", game_id).as_str())
+        .header("cookie", cookie_header(player_id))
+        .handshake(routes.clone())
+        .await
+        .unwrap()
+}
+
+/// Creates a game with a player in each team group, each connected over a WebSocket in the order
+/// of `PLAYERS`, and starts it.
+async fn started_game<F>(routes: &F) -> (String, Vec<WsClient>)
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply + Send,
+{
+    started_game_with_settings(routes, Default::default()).await
+}
+
+async fn started_game_with_settings<F>(
+    routes: &F,
+    settings: GameSettings,
+) -> (String, Vec<WsClient>)
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply + Send,
+{
+    let game_id = new_game_with_request(
+        routes,
+        HOST,
+        NewGameRequest {
+            player_name: format!("{}_name", HOST),
+            word_list: None,
+            seed: None,
+            settings,
+        },
+    )
+    .await;
+    let mut clients = vec![];
+    for (player_id, group) in PLAYERS {
+        if player_id != HOST {
+            join_game(routes, player_id, game_id.as_str()).await;
+        }
+        let mut client = connect(routes, player_id, game_id.as_str()).await;
+        send_request(
+            &mut client,
+            0,
+            EventRequest::MovePlayer {
+                new_group: group.clone(),
+            },
+        )
+        .await;
+        wait_for_state(&mut client, |view| view.this_player.group == group).await;
+        clients.push(client);
+    }
+    send_request(&mut clients[0], 0, EventRequest::StartGame).await;
+    (game_id, clients)
+}
+
+async fn send_request(client: &mut WsClient, request_id: RequestId, request: EventRequest) {
+    let message = ClientMessage::EventRequest {
+        request_id: Some(request_id),
+        request,
+    };
+    client
+        .send_text(serde_json::to_string(&message).unwrap())
+        .await;
+}
+
+async fn recv_message(client: &mut WsClient) -> ServerMessage {
+    let message = tokio::time::timeout(RECV_TIMEOUT, client.recv())
+        .await
+        .expect("Timed out waiting for a message")
+        .unwrap();
+    serde_json::from_str(message.to_str().unwrap()).unwrap()
+}
+
+/// Skips messages until a state update matching the predicate arrives.
+async fn wait_for_state(client: &mut WsClient, predicate: impl Fn(&GameView) -> bool) -> GameView {
+    loop {
+        match recv_message(client).await {
+            ServerMessage::StateUpdate(view) if predicate(&view) => return *view,
+            ServerMessage::Error(err) => panic!("Unexpected error {:?}", err),
+            _ => {}
+        }
+    }
+}
+
+async fn wait_for_error(client: &mut WsClient) -> common::api::v1::models::ErrorResponse {
+    loop {
+        if let ServerMessage::Error(err) = recv_message(client).await {
+            return err;
+        }
+    }
+}
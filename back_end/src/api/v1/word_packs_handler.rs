@@ -0,0 +1,16 @@
+use crate::game_notifier::GameNotifier;
+use crate::game_repo::GameStore;
+use crate::game_service::GameService;
+use std::sync::Arc;
+use warp::Filter;
+
+/// Lists the word lists that new games can choose from.
+pub fn route<S: GameStore, N: GameNotifier>(
+    game_service: Arc<GameService<S, N>>,
+) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+    Filter::boxed(
+        warp::get()
+            .and(warp::path!("word-packs"))
+            .map(move || warp::reply::json(&game_service.word_packs())),
+    )
+}
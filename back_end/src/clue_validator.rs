@@ -0,0 +1,217 @@
+use crate::game::{CodeNamesError, Result};
+use crate::word_lists::normalize;
+use common::api::v1::models::{Clue, ClueCount, GameSettings};
+
+/// The highest number of tiles a clue can refer to.
+pub const MAX_CLUE_COUNT: u8 = 9;
+
+/// English suffixes that are stripped to find a word's stem, with what they are replaced by.
+const SUFFIXES: [(&str, &str); 7] = [
+    ("ies", "y"),
+    ("ing", ""),
+    ("es", ""),
+    ("ed", ""),
+    ("er", ""),
+    ("ly", ""),
+    ("s", ""),
+];
+
+/// Endings after which a plural takes "es" rather than just "s", as in "glasses" or "churches".
+const ES_PLURAL_ENDINGS: [&str; 5] = ["s", "x", "z", "ch", "sh"];
+
+/// Stems shorter than this are too short to compare, so the suffix is kept.
+const MIN_STEM_LENGTH: usize = 3;
+
+/// Checks that a clue is a single word (unless the host allows compound clues) with a sensible
+/// count, and that it doesn't give away any of the words still on the board.
+pub fn validate_clue<'a>(
+    clue: &Clue,
+    board_words: impl IntoIterator<Item = &'a str>,
+    settings: &GameSettings,
+) -> Result<()> {
+    let word = clue.word.trim();
+    if word.is_empty() {
+        return Err(CodeNamesError::EmptyClueError);
+    }
+    if let ClueCount::Number(count) = clue.count {
+        // A clue for no tiles must be given as `ClueCount::Zero`.
+        if count == 0 || count > MAX_CLUE_COUNT {
+            return Err(CodeNamesError::ClueCountOutOfBoundsError { count });
+        }
+    }
+    let parts = split_words(word);
+    if parts.len() > 1 && !settings.allow_compound_clues {
+        return Err(CodeNamesError::CompoundClueError);
+    }
+    let clue_parts: Vec<String> = parts.iter().map(|part| normalize(part)).collect();
+    for board_word in board_words {
+        if split_words(board_word).iter().any(|board_part| {
+            let board_part = normalize(board_part);
+            clue_parts
+                .iter()
+                .any(|clue_part| is_form_of(clue_part, &board_part))
+        }) {
+            return Err(CodeNamesError::ClueIsBoardWordError {
+                word: board_word.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Splits on whitespace and hyphens, which both join words into a compound.
+fn split_words(word: &str) -> Vec<&str> {
+    word.split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Whether one normalized word is the other, or a simple form of it such as its plural. Stems are
+/// only compared against whole words, since two words can share a stem without being related, like
+/// "water" and "watt".
+fn is_form_of(a: &str, b: &str) -> bool {
+    a == b || stems(a).iter().any(|stem| stem == b) || stems(b).iter().any(|stem| stem == a)
+}
+
+/// Reduces a normalized word to the rough stems it could have, so that plurals and other simple
+/// forms of a word compare equal to it. There can be several, since "horses" and "buses" only
+/// differ in whether the "e" belongs to the stem, as do "fired" and "turned".
+fn stems(word: &str) -> Vec<String> {
+    let mut stems = vec![];
+    for (suffix, replacement) in SUFFIXES {
+        let Some(base) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        // Words like "glass" are not plurals, and "fires" only drops the "s".
+        if (suffix == "s" && base.ends_with('s'))
+            || (suffix == "es"
+                && !ES_PLURAL_ENDINGS
+                    .iter()
+                    .any(|ending| base.ends_with(ending)))
+        {
+            continue;
+        }
+        if base.chars().count() + replacement.len() < MIN_STEM_LENGTH {
+            continue;
+        }
+        let mut chars: Vec<char> = base.chars().chain(replacement.chars()).collect();
+        if ["ing", "ed", "er"].contains(&suffix) {
+            let last = chars[chars.len() - 1];
+            if chars.len() > MIN_STEM_LENGTH
+                && last.is_alphabetic()
+                && last == chars[chars.len() - 2]
+            {
+                // "running" and "run" should have the same stem, so a doubled final letter is
+                // dropped.
+                chars.pop();
+            } else if last != 'e' {
+                // "firing" drops the "e" of "fire" before its suffix.
+                stems.push(chars.iter().chain(['e'].iter()).collect());
+            }
+        }
+        stems.push(chars.into_iter().collect());
+    }
+    stems
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clue_validator::*;
+
+    const BOARD: [&str; 7] = ["RUN", "SPY", "GLASS", "ICE CREAM", "FIRE", "HORSE", "GAME"];
+
+    #[test]
+    fn when_clue_is_a_form_of_a_board_word_then_it_is_rejected() {
+        for word in [
+            "run", "Runs", "RUNNING", "spies", "glasses", "creams", "fires", "fired", "firing",
+            "horses", "horsing", "gamer", "games",
+        ] {
+            let result = validate_clue(&clue(word, 1), BOARD, &GameSettings::default());
+
+            assert!(
+                matches!(result, Err(CodeNamesError::ClueIsBoardWordError { .. })),
+                "{} should be rejected",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn when_clue_is_unrelated_to_board_words_then_it_is_accepted() -> Result<()> {
+        for word in ["rung", "spa", "glad", "  ocean "] {
+            validate_clue(&clue(word, 2), BOARD, &GameSettings::default())?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_clue_is_a_board_word_with_folded_case_then_it_is_rejected() {
+        for (word, board_word) in [("Straße", "STRASSE"), ("Fluß", "FLUSS")] {
+            let result = validate_clue(&clue(word, 1), [board_word], &GameSettings::default());
+
+            assert!(matches!(
+                result,
+                Err(CodeNamesError::ClueIsBoardWordError { word }) if word == board_word
+            ));
+        }
+    }
+
+    #[test]
+    fn when_clue_only_shares_a_stem_with_a_board_word_then_it_is_accepted() -> Result<()> {
+        for (word, board_word) in [("water", "WATT"), ("belly", "BELL"), ("panes", "PAN")] {
+            validate_clue(&clue(word, 1), [board_word], &GameSettings::default())?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_clue_has_several_words_then_host_must_allow_compounds() -> Result<()> {
+        let result = validate_clue(&clue("sea lion", 2), BOARD, &GameSettings::default());
+        assert!(matches!(result, Err(CodeNamesError::CompoundClueError)));
+
+        let settings = GameSettings {
+            allow_compound_clues: true,
+            ..Default::default()
+        };
+        validate_clue(&clue("sea-lion", 2), BOARD, &settings)?;
+        // Each part of a compound clue must still avoid the board words.
+        assert!(matches!(
+            validate_clue(&clue("running water", 2), BOARD, &settings),
+            Err(CodeNamesError::ClueIsBoardWordError { word }) if word == "RUN"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_clue_is_empty_or_count_out_of_bounds_then_it_is_rejected() -> Result<()> {
+        let settings = GameSettings::default();
+        assert!(matches!(
+            validate_clue(&clue("  ", 1), BOARD, &settings),
+            Err(CodeNamesError::EmptyClueError)
+        ));
+        assert!(matches!(
+            validate_clue(&clue("ocean", MAX_CLUE_COUNT + 1), BOARD, &settings),
+            Err(CodeNamesError::ClueCountOutOfBoundsError { count }) if count == MAX_CLUE_COUNT + 1
+        ));
+        assert!(matches!(
+            validate_clue(&clue("ocean", 0), BOARD, &settings),
+            Err(CodeNamesError::ClueCountOutOfBoundsError { count: 0 })
+        ));
+        validate_clue(&clue("ocean", MAX_CLUE_COUNT), BOARD, &settings)?;
+        for count in [ClueCount::Zero, ClueCount::Unlimited] {
+            let clue = Clue {
+                word: "ocean".to_string(),
+                count,
+            };
+            validate_clue(&clue, BOARD, &settings)?;
+        }
+        Ok(())
+    }
+
+    fn clue(word: &str, count: u8) -> Clue {
+        Clue {
+            word: word.to_string(),
+            count: ClueCount::Number(count),
+        }
+    }
+}
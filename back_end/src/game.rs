@@ -1,67 +1,200 @@
 use common::api::v1::models::{
-    Action, Clue, GameEvent, Group, Guess, Player, Team, TeamColour, Teams, TileColour,
+    Action, Clue, EndTurn, ErrorCode, GameEvent, GameOverReason, GameResult, GameSettings, Group,
+    Guess, Pause, Player, Resume, SeriesScore, Swap, Team, TeamColour, Teams, TileColour, Timeout,
+    WordList,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::clue_validator::{self, MAX_CLUE_COUNT};
+use crate::word_lists::{DEFAULT_WORD_LIST, MAX_CUSTOM_WORDS, MAX_WORD_LENGTH, MIN_WORDS};
+
+/// Players are identified by their player ID, which is the secret that authenticates them, so it
+/// must never be shown to other players. Instead, `teams` is keyed by each player's public ID.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Game {
     pub is_started: bool,
     pub tiles: [Tile; 25],
+    /// The language of the words on the tiles, if known.
+    pub language: Option<String>,
+    /// The seed the tiles and first turn were generated from.
+    pub seed: u64,
+    /// The word list the tiles were drawn from, so that a rematch can deal a new board. Only a
+    /// host's own words are stored in full.
+    pub word_list: WordList,
+    pub settings: GameSettings,
     pub teams: Teams,
+    /// Maps each player's ID to their public ID.
+    pub public_ids: HashMap<String, String>,
     pub host_id: String,
     pub team_turn: TeamColour,
     pub next_action: Action,
     pub history: Vec<GameEvent>,
+    pub result: Option<GameResult>,
+    pub series: SeriesScore,
+    /// The groups that players have asked to move to during the game, keyed by public ID.
+    pub pending_swaps: HashMap<String, Group>,
+    /// When the current turn ends on its own, in milliseconds since the Unix epoch.
+    pub deadline: Option<u64>,
+    /// When the host paused the game, in milliseconds since the Unix epoch, if it is paused.
+    pub paused_at: Option<u64>,
+    /// The player IDs of the players the host has kicked, who may not join again.
+    pub kicked_ids: HashSet<String>,
 }
 
 impl Game {
-    pub fn new(tiles: [Tile; 25], host_id: String, first_turn: TeamColour) -> Game {
+    pub fn new(tiles: [Tile; 25], seed: u64, host_id: String, first_turn: TeamColour) -> Game {
         Game {
             is_started: false,
             tiles,
+            language: None,
+            seed,
+            word_list: WordList::Named(DEFAULT_WORD_LIST.to_string()),
+            settings: Default::default(),
             teams: Teams {
                 blue: Default::default(),
                 red: Default::default(),
                 spectators: Default::default(),
             },
+            public_ids: Default::default(),
             host_id,
             team_turn: first_turn,
             next_action: Action::Clue,
             history: vec![],
+            result: None,
+            series: Default::default(),
+            pending_swaps: Default::default(),
+            deadline: None,
+            paused_at: None,
+            kicked_ids: Default::default(),
         }
     }
 
-    pub fn start(&mut self, player_id: &str) -> Result<()> {
+    pub fn start(&mut self, player_id: &str, now: u64) -> Result<()> {
         self.validate_game_has_not_started()?;
-        if self.host_id != player_id {
-            return Err(CodeNamesError::NotHostError);
-        }
+        self.validate_host(player_id)?;
         self.validate_team(&self.teams.blue)?;
         self.validate_team(&self.teams.red)?;
         self.is_started = true;
+        self.update_deadline(now);
         Ok(())
     }
 
     pub fn player_exists(&self, player_id: &str) -> bool {
-        self.teams.spectators.contains_key(player_id)
-            || self.teams.blue.spy_masters.contains_key(player_id)
-            || self.teams.blue.guessers.contains_key(player_id)
-            || self.teams.red.spy_masters.contains_key(player_id)
-            || self.teams.red.guessers.contains_key(player_id)
+        self.public_ids.contains_key(player_id)
+    }
+
+    pub fn public_id(&self, player_id: &str) -> Result<&str> {
+        self.public_ids
+            .get(player_id)
+            .map(String::as_str)
+            .ok_or(CodeNamesError::NoSuchPlayerError)
+    }
+
+    pub fn player(&self, player_id: &str) -> Option<&Player> {
+        let public_id = self.public_ids.get(player_id)?;
+        self.teams
+            .spectators
+            .get(public_id)
+            .or_else(|| self.teams.blue.guessers.get(public_id))
+            .or_else(|| self.teams.blue.spy_masters.get(public_id))
+            .or_else(|| self.teams.red.guessers.get(public_id))
+            .or_else(|| self.teams.red.spy_masters.get(public_id))
+    }
+
+    pub fn has_players(&self) -> bool {
+        !(self.teams.spectators.is_empty()
+            && self.teams.blue.guessers.is_empty()
+            && self.teams.blue.spy_masters.is_empty()
+            && self.teams.red.guessers.is_empty()
+            && self.teams.red.spy_masters.is_empty())
     }
 
     pub fn add_player(&mut self, player_id: &str, player: Player) -> Result<()> {
         if self.player_exists(player_id) {
             return Err(CodeNamesError::PlayerAlreadyInGameError);
         }
-        self.teams.spectators.insert(player_id.to_string(), player);
+        if self.kicked_ids.contains(player_id) {
+            return Err(CodeNamesError::PlayerKickedError);
+        }
+        let public_id = uuid::Uuid::new_v4().simple().to_string();
+        self.public_ids
+            .insert(player_id.to_string(), public_id.clone());
+        self.teams.spectators.insert(public_id, player);
         Ok(())
     }
 
+    /// Moves the player to another group. Groups are locked while a game is in progress, as a
+    /// guesser who became a spy master would see the key, so players must ask the host instead.
     pub fn move_player(&mut self, player_id: &str, new_group: Group) -> Result<()> {
-        let mut player = self.get_player_group(player_id)?.remove(player_id).unwrap();
+        if self.is_started && !self.is_over() {
+            return Err(CodeNamesError::GameAlreadyStartedError);
+        }
+        let public_id = self.public_id(player_id)?.to_string();
+        self.move_to_group(public_id.as_str(), new_group)?;
+        Ok(())
+    }
+
+    /// Asks the host to move this player to another group while the game is in progress.
+    pub fn request_swap(&mut self, player_id: &str, new_group: Group) -> Result<()> {
+        self.validate_game_has_started()?;
+        self.validate_game_is_not_over()?;
+        let player = self
+            .player(player_id)
+            .ok_or(CodeNamesError::NoSuchPlayerError)?;
+        if player.group == new_group {
+            return Err(CodeNamesError::AlreadyInGroupError);
+        }
+        let public_id = self.public_id(player_id)?.to_string();
+        self.pending_swaps.insert(public_id, new_group);
+        Ok(())
+    }
+
+    /// Moves the player with the given public ID to the group they asked for, and records the
+    /// move in the history. Each team must keep a spy master and a guesser, or nobody could take
+    /// that team's turn.
+    pub fn approve_swap(&mut self, player_id: &str, swapped_public_id: &str) -> Result<()> {
+        self.validate_host(player_id)?;
+        self.validate_game_is_not_over()?;
+        let new_group = self
+            .pending_swaps
+            .get(swapped_public_id)
+            .cloned()
+            .ok_or(CodeNamesError::NoSuchSwapRequestError)?;
+        let (player_name, from) = self.move_to_group(swapped_public_id, new_group.clone())?;
+        if let Err(err) = self
+            .validate_team(&self.teams.blue)
+            .and_then(|_| self.validate_team(&self.teams.red))
+        {
+            self.move_to_group(swapped_public_id, from)?;
+            return Err(err);
+        }
+        self.pending_swaps.remove(swapped_public_id);
+        self.history.push(GameEvent::Swap(Swap {
+            player_name,
+            from,
+            to: new_group,
+        }));
+        Ok(())
+    }
+
+    pub fn deny_swap(&mut self, player_id: &str, swapped_public_id: &str) -> Result<()> {
+        self.validate_host(player_id)?;
+        self.pending_swaps
+            .remove(swapped_public_id)
+            .map(|_| ())
+            .ok_or(CodeNamesError::NoSuchSwapRequestError)
+    }
+
+    /// Moves a player between groups, returning their name and the group they left.
+    fn move_to_group(&mut self, public_id: &str, new_group: Group) -> Result<(String, Group)> {
+        let public_id = public_id.to_string();
+        let mut player = self
+            .get_player_group(public_id.as_str())?
+            .remove(public_id.as_str())
+            .unwrap();
         let group_to_move_to = match new_group {
             Group::Spectators => &mut self.teams.spectators,
             Group::BlueGuessers => &mut self.teams.blue.guessers,
@@ -69,40 +202,340 @@ impl Game {
             Group::RedGuessers => &mut self.teams.red.guessers,
             Group::RedSpyMasters => &mut self.teams.red.spy_masters,
         };
-        player.group = new_group;
-        group_to_move_to.insert(player_id.to_string(), player);
-        Ok(())
+        let old_group = std::mem::replace(&mut player.group, new_group);
+        let player_name = player.name.clone();
+        group_to_move_to.insert(public_id, player);
+        Ok((player_name, old_group))
     }
 
+    /// Removes the player from the game. If they were the host, another player becomes the host.
     pub fn remove_player(&mut self, player_id: &str) -> Result<()> {
-        self.get_player_group(player_id)?.remove(player_id).unwrap();
+        let public_id = self.public_id(player_id)?.to_string();
+        self.get_player_group(public_id.as_str())?
+            .remove(public_id.as_str())
+            .unwrap();
+        self.public_ids.remove(player_id);
+        self.pending_swaps.remove(public_id.as_str());
+        if self.host_id == player_id {
+            // Promote the player with the lowest public ID, so every server picks the same one.
+            let next_host_id = self
+                .public_ids
+                .iter()
+                .min_by(|(_, a), (_, b)| a.cmp(b))
+                .map(|(player_id, _)| player_id.clone());
+            if let Some(next_host_id) = next_host_id {
+                self.set_host(next_host_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the player with the given public ID the host instead.
+    pub fn transfer_host(&mut self, player_id: &str, new_host_public_id: &str) -> Result<()> {
+        self.validate_host(player_id)?;
+        let new_host_id = self.player_id(new_host_public_id)?;
+        if new_host_id == self.host_id {
+            return Err(CodeNamesError::AlreadyHostError);
+        }
+        self.set_host(new_host_id)
+    }
+
+    /// Removes the player with the given public ID from the game, for good.
+    pub fn kick_player(&mut self, player_id: &str, kicked_public_id: &str) -> Result<()> {
+        self.validate_host(player_id)?;
+        let kicked_id = self.player_id(kicked_public_id)?;
+        if kicked_id == self.host_id {
+            return Err(CodeNamesError::CannotKickHostError);
+        }
+        self.remove_player(kicked_id.as_str())?;
+        self.kicked_ids.insert(kicked_id);
         Ok(())
     }
 
-    pub fn provide_clue(&mut self, player_id: &str, clue: Clue) -> Result<()> {
+    pub fn provide_clue(&mut self, player_id: &str, clue: Clue, now: u64) -> Result<()> {
         self.validate_game_has_started()?;
+        self.validate_game_is_not_over()?;
+        self.validate_game_is_not_paused()?;
         self.validate_action(Action::Clue)?;
         self.validate_player(player_id)?;
-        self.history.push(GameEvent::Clue(clue));
+        // Words that have been guessed are covered up, so they can be used as clues again.
+        let revealed_tiles = self.revealed_tiles();
+        let board_words = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !revealed_tiles.contains(&(*index as u8)))
+            .map(|(_, tile)| tile.word.as_str());
+        clue_validator::validate_clue(&clue, board_words, &self.settings)?;
+        self.history.push(GameEvent::Clue(Clue {
+            word: clue.word.trim().to_string(),
+            ..clue
+        }));
         self.next_action = Action::Guess;
+        self.update_deadline(now);
         Ok(())
     }
 
-    pub fn guess(&mut self, player_id: &str, guess: Guess) -> Result<()> {
+    pub fn guess(&mut self, player_id: &str, guess: Guess, now: u64) -> Result<()> {
         self.validate_game_has_started()?;
+        self.validate_game_is_not_over()?;
+        self.validate_game_is_not_paused()?;
         self.validate_action(Action::Guess)?;
         self.validate_player(player_id)?;
         self.validate_tile_index(guess.tile_index)?;
+        self.validate_tile_not_revealed(guess.tile_index)?;
+        let tile_index = guess.tile_index;
         self.history.push(GameEvent::Guess(guess));
-        if !self.can_guess_more() {
-            self.next_action = Action::Clue;
-            self.team_turn = self.team_turn.other();
+        self.result = self.check_for_result(tile_index);
+        if self.is_over() {
+            self.deadline = None;
+        } else if !self.can_guess_more() {
+            self.pass_turn(now);
+        }
+        Ok(())
+    }
+
+    pub fn end_turn(&mut self, player_id: &str, now: u64) -> Result<()> {
+        self.validate_game_has_started()?;
+        self.validate_game_is_not_over()?;
+        self.validate_game_is_not_paused()?;
+        self.validate_action(Action::Guess)?;
+        let player_name = self.validate_player(player_id)?.name.clone();
+        // At least one guess must be made for each clue.
+        let last_move = self
+            .history
+            .iter()
+            .rev()
+            .find(|event| matches!(event, GameEvent::Clue(_) | GameEvent::Guess(_)));
+        if !matches!(last_move, Some(GameEvent::Guess(_))) {
+            return Err(CodeNamesError::NoGuessesMadeError);
+        }
+        self.history.push(GameEvent::EndTurn(EndTurn {
+            team: self.team_turn.clone(),
+            player_name,
+        }));
+        self.pass_turn(now);
+        Ok(())
+    }
+
+    pub fn forfeit(&mut self, player_id: &str) -> Result<()> {
+        self.validate_game_has_started()?;
+        self.validate_game_is_not_over()?;
+        let team = match self.player(player_id) {
+            Some(player) => match player.group {
+                Group::BlueGuessers | Group::BlueSpyMasters => TeamColour::Blue,
+                Group::RedGuessers | Group::RedSpyMasters => TeamColour::Red,
+                Group::Spectators => return Err(CodeNamesError::NotInTeamError),
+            },
+            None => return Err(CodeNamesError::NoSuchPlayerError),
+        };
+        self.result = Some(GameResult {
+            winner: team.other(),
+            reason: GameOverReason::Forfeit,
+        });
+        self.deadline = None;
+        self.paused_at = None;
+        Ok(())
+    }
+
+    /// Stops the clock, so that nobody runs out of time while someone has stepped away.
+    pub fn pause(&mut self, player_id: &str, now: u64) -> Result<()> {
+        self.validate_host(player_id)?;
+        self.validate_game_has_started()?;
+        self.validate_game_is_not_over()?;
+        self.validate_game_is_not_paused()?;
+        let player_name = self
+            .player(player_id)
+            .ok_or(CodeNamesError::NoSuchPlayerError)?
+            .name
+            .clone();
+        self.history.push(GameEvent::Pause(Pause { player_name }));
+        self.paused_at = Some(now);
+        Ok(())
+    }
+
+    /// Restarts the clock, giving the current team back the time they had left when the game was
+    /// paused.
+    pub fn resume(&mut self, player_id: &str, now: u64) -> Result<()> {
+        self.validate_host(player_id)?;
+        let Some(paused_at) = self.paused_at else {
+            return Err(CodeNamesError::GameNotPausedError);
+        };
+        let player_name = self
+            .player(player_id)
+            .ok_or(CodeNamesError::NoSuchPlayerError)?
+            .name
+            .clone();
+        self.history.push(GameEvent::Resume(Resume { player_name }));
+        self.deadline = self
+            .deadline
+            .map(|deadline| deadline + now.saturating_sub(paused_at));
+        self.paused_at = None;
+        Ok(())
+    }
+
+    /// Ends the turn of a team that has run out of time. Every server may try this once the
+    /// deadline passes, so it is rejected unless the current deadline has actually been reached.
+    pub fn expire_turn(&mut self, now: u64) -> Result<()> {
+        self.validate_game_is_not_paused()?;
+        match self.deadline {
+            Some(deadline) if deadline <= now => {}
+            _ => return Err(CodeNamesError::DeadlineNotReachedError),
         }
+        self.history.push(GameEvent::Timeout(Timeout {
+            team: self.team_turn.clone(),
+            action: self.next_action.clone(),
+        }));
+        self.pass_turn(now);
         Ok(())
     }
 
-    fn validate_team<'b>(&self, team: &Team) -> Result<()> {
-        if team.spy_masters.len() == 0 || team.guessers.len() == 0 {
+    /// Replaces the finished board with a new one, and counts the finished game towards the
+    /// series. Players stay in their groups, unless the spy masters are rotated, in which case each
+    /// team hands the role on to the next of its players.
+    pub fn rematch(
+        &mut self,
+        player_id: &str,
+        tiles: [Tile; 25],
+        seed: u64,
+        first_turn: TeamColour,
+        rotate_spy_masters: bool,
+        now: u64,
+    ) -> Result<()> {
+        self.validate_host(player_id)?;
+        let Some(result) = &self.result else {
+            return Err(CodeNamesError::GameNotOverError);
+        };
+        self.validate_team(&self.teams.blue)?;
+        self.validate_team(&self.teams.red)?;
+        self.series.record_win(&result.winner);
+        if rotate_spy_masters {
+            Self::rotate_spy_masters(
+                &mut self.teams.blue,
+                Group::BlueGuessers,
+                Group::BlueSpyMasters,
+            );
+            Self::rotate_spy_masters(
+                &mut self.teams.red,
+                Group::RedGuessers,
+                Group::RedSpyMasters,
+            );
+        }
+        self.tiles = tiles;
+        self.seed = seed;
+        self.team_turn = first_turn;
+        self.next_action = Action::Clue;
+        self.history.clear();
+        self.result = None;
+        self.pending_swaps.clear();
+        self.update_deadline(now);
+        Ok(())
+    }
+
+    /// The group whose players may act now, given whose turn it is and what they need to do next.
+    pub fn acting_group(&self) -> Group {
+        match (&self.team_turn, &self.next_action) {
+            (TeamColour::Blue, Action::Clue) => Group::BlueSpyMasters,
+            (TeamColour::Blue, Action::Guess) => Group::BlueGuessers,
+            (TeamColour::Red, Action::Clue) => Group::RedSpyMasters,
+            (TeamColour::Red, Action::Guess) => Group::RedGuessers,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Collects the indexes of every tile that has already been guessed.
+    pub fn revealed_tiles(&self) -> HashSet<u8> {
+        self.history
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::Guess(guess) => Some(guess.tile_index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Orders the team's players by public ID, and moves the spy master role along that order by
+    /// as many places as there are spy masters, so that every player gets a turn.
+    fn rotate_spy_masters(team: &mut Team, guessers: Group, spy_masters: Group) {
+        let spy_master_count = team.spy_masters.len();
+        let mut players: Vec<(String, Player)> = team
+            .spy_masters
+            .drain()
+            .chain(team.guessers.drain())
+            .collect();
+        players.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let was_spy_master: Vec<bool> = players
+            .iter()
+            .map(|(_, player)| player.group == spy_masters)
+            .collect();
+        let player_count = players.len();
+        for (index, (public_id, mut player)) in players.into_iter().enumerate() {
+            let previous_index = (index + player_count - spy_master_count) % player_count;
+            if was_spy_master[previous_index] {
+                player.group = spy_masters.clone();
+                team.spy_masters.insert(public_id, player);
+            } else {
+                player.group = guessers.clone();
+                team.guessers.insert(public_id, player);
+            }
+        }
+    }
+
+    /// Hands the turn to the other team's spy masters.
+    fn pass_turn(&mut self, now: u64) {
+        self.next_action = Action::Clue;
+        self.team_turn = self.team_turn.other();
+        self.update_deadline(now);
+    }
+
+    /// Starts the clock for the next action, if the game has a time limit for it.
+    fn update_deadline(&mut self, now: u64) {
+        let time_limit = match self.next_action {
+            Action::Clue => self.settings.clue_time_limit_secs,
+            Action::Guess => self.settings.guess_time_limit_secs,
+        };
+        self.deadline = time_limit
+            .filter(|_| self.is_started && !self.is_over())
+            .map(|time_limit| now + u64::from(time_limit.get()) * 1000);
+    }
+
+    /// Finds the player ID behind a public ID.
+    fn player_id(&self, public_id: &str) -> Result<String> {
+        self.public_ids
+            .iter()
+            .find(|(_, id)| id.as_str() == public_id)
+            .map(|(player_id, _)| player_id.clone())
+            .ok_or(CodeNamesError::NoSuchPlayerError)
+    }
+
+    /// Moves the host role to the given player, keeping `host_id` and each player's `is_host` in
+    /// step.
+    fn set_host(&mut self, new_host_id: String) -> Result<()> {
+        for (player_id, is_host) in [(self.host_id.clone(), false), (new_host_id.clone(), true)] {
+            if let Some(public_id) = self.public_ids.get(player_id.as_str()).cloned() {
+                self.get_player_group(public_id.as_str())?
+                    .get_mut(public_id.as_str())
+                    .unwrap()
+                    .is_host = is_host;
+            }
+        }
+        self.host_id = new_host_id;
+        Ok(())
+    }
+
+    fn validate_host(&self, player_id: &str) -> Result<()> {
+        if self.host_id == player_id {
+            Ok(())
+        } else {
+            Err(CodeNamesError::NotHostError)
+        }
+    }
+
+    fn validate_team(&self, team: &Team) -> Result<()> {
+        if team.spy_masters.is_empty() || team.guessers.is_empty() {
             Err(CodeNamesError::NotEnoughPlayersError)
         } else {
             Ok(())
@@ -110,56 +543,21 @@ impl Game {
     }
 
     fn validate_player(&self, player_id: &str) -> Result<&Player> {
-        let team = match self.team_turn {
-            TeamColour::Red => &self.teams.red,
-            TeamColour::Blue => &self.teams.blue,
-        };
-        match self.next_action {
-            Action::Clue => &team.spy_masters,
-            Action::Guess => &team.guessers,
-        }
-        .get(player_id)
-        .ok_or_else(|| {
-            let exp_group: Group = match self.next_action {
-                Action::Clue => match self.team_turn {
-                    TeamColour::Red => Group::RedSpyMasters,
-                    TeamColour::Blue => Group::RedGuessers,
-                },
-                Action::Guess => match self.team_turn {
-                    TeamColour::Red => Group::BlueSpyMasters,
-                    TeamColour::Blue => Group::BlueGuessers,
-                },
-            };
-            let actual: Option<Group> = if self.teams.spectators.contains_key(player_id) {
-                Some(Group::Spectators)
-            } else if self.teams.blue.spy_masters.contains_key(player_id) {
-                Some(Group::BlueSpyMasters)
-            } else if self.teams.blue.guessers.contains_key(player_id) {
-                Some(Group::BlueGuessers)
-            } else if self.teams.red.spy_masters.contains_key(player_id) {
-                Some(Group::RedSpyMasters)
-            } else if self.teams.red.guessers.contains_key(player_id) {
-                Some(Group::RedGuessers)
-            } else {
-                None
-            };
-            match actual {
-                Some(act_group) => {
-                    if exp_group != act_group {
-                        CodeNamesError::IllegalPlayerGroupError {
-                            exp_group,
-                            act_group,
-                        }
-                    } else {
-                        panic!("The player is in the correct group, but could not be found")
-                    }
-                }
-                None => CodeNamesError::NoSuchPlayerError,
-            }
-        })
+        let player = self
+            .player(player_id)
+            .ok_or(CodeNamesError::NoSuchPlayerError)?;
+        let exp_group = self.acting_group();
+        if player.group == exp_group {
+            Ok(player)
+        } else {
+            Err(CodeNamesError::IllegalPlayerGroupError {
+                exp_group,
+                act_group: player.group.clone(),
+            })
+        }
     }
 
-    fn validate_game_has_not_started<'b>(&self) -> Result<()> {
+    fn validate_game_has_not_started(&self) -> Result<()> {
         if self.is_started {
             Err(CodeNamesError::GameAlreadyStartedError)
         } else {
@@ -167,7 +565,7 @@ impl Game {
         }
     }
 
-    fn validate_game_has_started<'b>(&self) -> Result<()> {
+    fn validate_game_has_started(&self) -> Result<()> {
         if !self.is_started {
             Err(CodeNamesError::GameNotStartedError)
         } else {
@@ -175,7 +573,23 @@ impl Game {
         }
     }
 
-    fn validate_action<'b>(&self, request_action: Action) -> Result<()> {
+    fn validate_game_is_not_over(&self) -> Result<()> {
+        if self.is_over() {
+            Err(CodeNamesError::GameOverError)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn validate_game_is_not_paused(&self) -> Result<()> {
+        if self.paused_at.is_some() {
+            Err(CodeNamesError::GamePausedError)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn validate_action(&self, request_action: Action) -> Result<()> {
         if self.next_action != request_action {
             Err(CodeNamesError::InvalidActionError)
         } else {
@@ -191,7 +605,15 @@ impl Game {
         }
     }
 
-    fn get_player_group(&mut self, player_id: &str) -> Result<&mut HashMap<String, Player>> {
+    fn validate_tile_not_revealed(&self, tile_index: u8) -> Result<()> {
+        if self.revealed_tiles().contains(&tile_index) {
+            Err(CodeNamesError::TileAlreadyRevealedError { tile_index })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_player_group(&mut self, public_id: &str) -> Result<&mut HashMap<String, Player>> {
         fn contains_player<'a>(
             players: &'a mut HashMap<String, Player>,
             player_id: &str,
@@ -202,11 +624,11 @@ impl Game {
                 None
             }
         }
-        contains_player(&mut self.teams.spectators, player_id)
-            .or_else(|| contains_player(&mut self.teams.blue.guessers, player_id))
-            .or_else(|| contains_player(&mut self.teams.blue.spy_masters, player_id))
-            .or_else(|| contains_player(&mut self.teams.red.guessers, player_id))
-            .or_else(|| contains_player(&mut self.teams.red.spy_masters, player_id))
+        contains_player(&mut self.teams.spectators, public_id)
+            .or_else(|| contains_player(&mut self.teams.blue.guessers, public_id))
+            .or_else(|| contains_player(&mut self.teams.blue.spy_masters, public_id))
+            .or_else(|| contains_player(&mut self.teams.red.guessers, public_id))
+            .or_else(|| contains_player(&mut self.teams.red.spy_masters, public_id))
             .ok_or(CodeNamesError::NoSuchPlayerError)
     }
 
@@ -236,21 +658,62 @@ impl Game {
                                     return false;
                                 }
                             }
-                            TileColour::Grey => return false,
-                            TileColour::Black => todo!("Implement game over"),
+                            TileColour::Grey | TileColour::Black => return false,
                         }
                     }
                     guess_count += 1;
                 }
+                GameEvent::EndTurn(_)
+                | GameEvent::Swap(_)
+                | GameEvent::Timeout(_)
+                | GameEvent::Pause(_)
+                | GameEvent::Resume(_) => {}
             }
         }
         let current_clue =
             current_clue.expect("Cannot be guessing at all if no clues have been provided");
-        if current_clue.count < guess_count {
-            // The maximum amount of guesses have been made.
-            return false;
+        // Whether the maximum amount of guesses have been made.
+        match current_clue.count.max_guesses() {
+            Some(max_guesses) => guess_count < max_guesses,
+            None => true,
+        }
+    }
+
+    /// Checks whether the guess just made on the given tile has ended the game.
+    fn check_for_result(&self, tile_index: u8) -> Option<GameResult> {
+        let team = match self.get_tile_colour(tile_index) {
+            TileColour::Black => {
+                return Some(GameResult {
+                    winner: self.team_turn.other(),
+                    reason: GameOverReason::Assassin,
+                })
+            }
+            TileColour::Grey => return None,
+            TileColour::Red => TeamColour::Red,
+            TileColour::Blue => TeamColour::Blue,
+        };
+        // Uncovering an opponent's tile can also win the game for the opponent.
+        if self.all_agents_found(&team) {
+            Some(GameResult {
+                winner: team,
+                reason: GameOverReason::AllAgentsFound,
+            })
+        } else {
+            None
         }
-        true
+    }
+
+    fn all_agents_found(&self, team: &TeamColour) -> bool {
+        let team_colour = match team {
+            TeamColour::Red => TileColour::Red,
+            TeamColour::Blue => TileColour::Blue,
+        };
+        let revealed_tiles = self.revealed_tiles();
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| tile.colour == team_colour)
+            .all(|(index, _)| revealed_tiles.contains(&(index as u8)))
     }
 
     fn get_tile_colour(&self, tile_index: u8) -> &TileColour {
@@ -262,6 +725,14 @@ impl Game {
     }
 }
 
+/// The current time, in milliseconds since the Unix epoch.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System clock is set before the Unix epoch")
+        .as_millis() as u64
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Tile {
     pub word: String,
@@ -269,24 +740,122 @@ pub struct Tile {
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum CodeNamesError {
+    AlreadyHostError,
+    AlreadyInGroupError,
+    CannotKickHostError,
+    ClueCountOutOfBoundsError { count: u8 },
+    ClueIsBoardWordError { word: String },
+    CompoundClueError,
+    ConcurrentModificationError,
+    DeadlineNotReachedError,
+    DuplicateWordError { word: String },
+    EmptyClueError,
     GameAlreadyStartedError,
+    GameNotOverError,
+    GameNotPausedError,
     GameNotStartedError,
+    GameOverError,
+    GamePausedError,
     IllegalPlayerGroupError { exp_group: Group, act_group: Group },
     InvalidActionError,
+    InvalidWordError { word: String },
+    NoGuessesMadeError,
     NoSuchGameError,
     NoSuchPlayerError,
+    NoSuchSwapRequestError,
+    NoSuchWordListError { name: String },
     NotEnoughPlayersError,
+    NotEnoughWordsError { count: usize },
     NotHostError,
+    NotInTeamError,
     PlayerAlreadyInGameError,
+    PlayerKickedError,
+    StorageError(String),
+    TileAlreadyRevealedError { tile_index: u8 },
     TileIndexOutOfBoundsError { tile_index: u8 },
+    TooManyWordsError { count: usize },
+}
+
+impl CodeNamesError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            CodeNamesError::AlreadyHostError => ErrorCode::AlreadyHost,
+            CodeNamesError::AlreadyInGroupError => ErrorCode::AlreadyInGroup,
+            CodeNamesError::CannotKickHostError => ErrorCode::CannotKickHost,
+            CodeNamesError::ClueCountOutOfBoundsError { .. } => ErrorCode::ClueCountOutOfBounds,
+            CodeNamesError::ClueIsBoardWordError { .. } => ErrorCode::ClueIsBoardWord,
+            CodeNamesError::CompoundClueError => ErrorCode::CompoundClue,
+            CodeNamesError::ConcurrentModificationError => ErrorCode::ConcurrentModification,
+            CodeNamesError::DeadlineNotReachedError => ErrorCode::DeadlineNotReached,
+            CodeNamesError::DuplicateWordError { .. } => ErrorCode::DuplicateWord,
+            CodeNamesError::EmptyClueError => ErrorCode::EmptyClue,
+            CodeNamesError::GameAlreadyStartedError => ErrorCode::GameAlreadyStarted,
+            CodeNamesError::GameNotOverError => ErrorCode::GameNotOver,
+            CodeNamesError::GameNotPausedError => ErrorCode::GameNotPaused,
+            CodeNamesError::GameNotStartedError => ErrorCode::GameNotStarted,
+            CodeNamesError::GameOverError => ErrorCode::GameOver,
+            CodeNamesError::GamePausedError => ErrorCode::GamePaused,
+            CodeNamesError::IllegalPlayerGroupError { .. } => ErrorCode::IllegalPlayerGroup,
+            CodeNamesError::InvalidActionError => ErrorCode::InvalidAction,
+            CodeNamesError::InvalidWordError { .. } => ErrorCode::InvalidWord,
+            CodeNamesError::NoGuessesMadeError => ErrorCode::NoGuessesMade,
+            CodeNamesError::NoSuchGameError => ErrorCode::NoSuchGame,
+            CodeNamesError::NoSuchPlayerError => ErrorCode::NoSuchPlayer,
+            CodeNamesError::NoSuchSwapRequestError => ErrorCode::NoSuchSwapRequest,
+            CodeNamesError::NoSuchWordListError { .. } => ErrorCode::NoSuchWordList,
+            CodeNamesError::NotEnoughPlayersError => ErrorCode::NotEnoughPlayers,
+            CodeNamesError::NotEnoughWordsError { .. } => ErrorCode::NotEnoughWords,
+            CodeNamesError::NotHostError => ErrorCode::NotHost,
+            CodeNamesError::NotInTeamError => ErrorCode::NotInTeam,
+            CodeNamesError::PlayerAlreadyInGameError => ErrorCode::PlayerAlreadyInGame,
+            CodeNamesError::PlayerKickedError => ErrorCode::PlayerKicked,
+            CodeNamesError::StorageError(_) => ErrorCode::Storage,
+            CodeNamesError::TileAlreadyRevealedError { .. } => ErrorCode::TileAlreadyRevealed,
+            CodeNamesError::TileIndexOutOfBoundsError { .. } => ErrorCode::TileIndexOutOfBounds,
+            CodeNamesError::TooManyWordsError { .. } => ErrorCode::TooManyWords,
+        }
+    }
 }
 
 impl Display for CodeNamesError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            CodeNamesError::AlreadyHostError => write!(f, "Player is already the host"),
+            CodeNamesError::AlreadyInGroupError => write!(f, "Player is already in this group"),
+            CodeNamesError::CannotKickHostError => {
+                write!(
+                    f,
+                    "The host cannot be kicked, they must leave or hand over instead"
+                )
+            }
+            CodeNamesError::ClueCountOutOfBoundsError { count } => write!(
+                f,
+                "Clues must refer to between 1 and {} tiles, not {}; a clue for no tiles must be \
+                 given as a zero clue",
+                MAX_CLUE_COUNT, count
+            ),
+            CodeNamesError::ClueIsBoardWordError { word } => {
+                write!(f, "Clue is too close to a word on the board: {}", word)
+            }
+            CodeNamesError::CompoundClueError => {
+                write!(f, "Clue must be a single word in this game")
+            }
+            CodeNamesError::ConcurrentModificationError => {
+                write!(f, "Game was modified by too many other requests at once")
+            }
+            CodeNamesError::DeadlineNotReachedError => write!(f, "Turn has not run out of time"),
+            CodeNamesError::DuplicateWordError { word } => {
+                write!(f, "Word appears more than once in the word list: {}", word)
+            }
+            CodeNamesError::EmptyClueError => write!(f, "Clue must not be empty"),
             CodeNamesError::GameAlreadyStartedError => write!(f, "Game has already started"),
+            CodeNamesError::GameNotOverError => write!(f, "Game is not over yet"),
+            CodeNamesError::GameNotPausedError => write!(f, "Game is not paused"),
             CodeNamesError::GameNotStartedError => write!(f, "Game has not yet started"),
+            CodeNamesError::GameOverError => write!(f, "Game is already over"),
+            CodeNamesError::GamePausedError => write!(f, "Game is paused"),
             CodeNamesError::IllegalPlayerGroupError {
                 exp_group,
                 act_group,
@@ -306,18 +875,54 @@ impl Display for CodeNamesError {
                 )
             }
             CodeNamesError::InvalidActionError => write!(f, "Cannot perform this action"),
+            CodeNamesError::InvalidWordError { word } => write!(
+                f,
+                "Words must be between 1 and {} characters long: {:?}",
+                MAX_WORD_LENGTH, word
+            ),
+            CodeNamesError::NoGuessesMadeError => {
+                write!(f, "At least one guess must be made before ending the turn")
+            }
             CodeNamesError::NoSuchGameError => write!(f, "Game does not exist"),
             CodeNamesError::NoSuchPlayerError => write!(f, "Player is not in this game"),
+            CodeNamesError::NoSuchSwapRequestError => {
+                write!(f, "Player has not asked to change groups")
+            }
+            CodeNamesError::NoSuchWordListError { name } => {
+                write!(f, "Word list does not exist: {}", name)
+            }
             CodeNamesError::NotEnoughPlayersError => {
                 write!(f, "Not enough players to perform this action")
             }
+            CodeNamesError::NotEnoughWordsError { count } => write!(
+                f,
+                "Word list must have at least {} words, but has {}",
+                MIN_WORDS, count
+            ),
             CodeNamesError::NotHostError => {
                 write!(f, "Player must be the host to perform this action")
             }
+            CodeNamesError::NotInTeamError => {
+                write!(f, "Player must be in a team to perform this action")
+            }
             CodeNamesError::PlayerAlreadyInGameError => write!(f, "Player is already in this game"),
+            CodeNamesError::PlayerKickedError => {
+                write!(f, "Player was kicked from this game and may not join again")
+            }
+            CodeNamesError::StorageError(err) => {
+                write!(f, "Failed to access game storage: {}", err)
+            }
+            CodeNamesError::TileAlreadyRevealedError { tile_index } => {
+                write!(f, "Tile has already been revealed: {}", tile_index)
+            }
             CodeNamesError::TileIndexOutOfBoundsError { tile_index } => {
                 write!(f, "Invalid tile index: {}", tile_index)
             }
+            CodeNamesError::TooManyWordsError { count } => write!(
+                f,
+                "Word list can have at most {} words, but has {}",
+                MAX_CUSTOM_WORDS, count
+            ),
         }
     }
 }
@@ -326,19 +931,82 @@ impl std::error::Error for CodeNamesError {}
 
 pub type Result<T> = core::result::Result<T, CodeNamesError>;
 
+/// A game with a full set of players, shared by every test that needs one.
+#[cfg(test)]
+pub mod test_fixtures {
+    use crate::game::{Game, Tile};
+    use common::api::v1::models::{Clue, ClueCount, Group, Player, TeamColour, TileColour};
+    use std::array::from_fn;
+
+    /// The time that every request in tests is made at, in milliseconds since the Unix epoch.
+    pub const NOW: u64 = 1_000_000;
+
+    pub const BLUE_SPY_MASTER: &str = "blue_spy_master";
+    pub const BLUE_GUESSER: &str = "blue_guesser";
+    pub const RED_SPY_MASTER: &str = "red_spy_master";
+    pub const RED_GUESSER: &str = "red_guesser";
+    pub const SPECTATOR: &str = "spectator";
+    pub const PLAYERS: [(&str, Group); 4] = [
+        (BLUE_SPY_MASTER, Group::BlueSpyMasters),
+        (BLUE_GUESSER, Group::BlueGuessers),
+        (RED_SPY_MASTER, Group::RedSpyMasters),
+        (RED_GUESSER, Group::RedGuessers),
+    ];
+
+    /// Creates a started game where it is red's turn to give a clue.
+    ///
+    /// Tile 0 is the assassin, tiles 1-9 are red, tiles 10-17 are blue and the rest are grey.
+    pub fn started_game() -> Game {
+        let tiles: [Tile; 25] = from_fn(|index| Tile {
+            word: format!("word_{}", index),
+            colour: match index {
+                0 => TileColour::Black,
+                1..=9 => TileColour::Red,
+                10..=17 => TileColour::Blue,
+                _ => TileColour::Grey,
+            },
+        });
+        let mut game = Game::new(tiles, 0, BLUE_SPY_MASTER.to_string(), TeamColour::Red);
+        for (player_id, group) in PLAYERS {
+            game.add_player(player_id, player(player_id)).unwrap();
+            game.move_player(player_id, group).unwrap();
+        }
+        game.start(BLUE_SPY_MASTER, NOW).unwrap();
+        game
+    }
+
+    pub fn player(player_id: &str) -> Player {
+        Player {
+            name: format!("{}_name", player_id),
+            group: Group::Spectators,
+            is_host: player_id == BLUE_SPY_MASTER,
+        }
+    }
+
+    pub fn clue(count: u8) -> Clue {
+        Clue {
+            word: "clue".to_string(),
+            count: ClueCount::Number(count),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use common::api::v1::models::Player;
+    use common::api::v1::models::{ClueCount, Player};
 
+    use crate::game::test_fixtures::*;
     use crate::game::*;
+    use proptest::prelude::*;
     use std::array::from_fn;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
+    use std::num::NonZeroU32;
 
     #[test]
     fn when_enough_players_then_game_can_be_started() -> Result<()> {
         let tiles: [Tile; 25] = random_tiles();
         let player1_id = "player_1";
-        let mut game: Game = Game::new(tiles, player1_id.to_string(), TeamColour::Red);
+        let mut game: Game = Game::new(tiles, 0, player1_id.to_string(), TeamColour::Red);
         let player1 = Player {
             name: "player_1_name".to_string(),
             group: Group::Spectators,
@@ -367,11 +1035,875 @@ mod tests {
         game.teams.red.spy_masters = HashMap::from([(player3_id.to_string(), player3)]);
         game.teams.red.guessers = HashMap::from([(player4_id.to_string(), player4)]);
         assert!(!game.is_started);
-        game.start(player1_id)?;
+        game.start(player1_id, NOW)?;
         assert!(game.is_started);
         Ok(())
     }
 
+    #[test]
+    fn when_assassin_guessed_then_other_team_wins() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 0 }, NOW)?;
+        assert_eq!(
+            game.result,
+            Some(GameResult {
+                winner: TeamColour::Blue,
+                reason: GameOverReason::Assassin,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_all_agents_found_then_team_wins() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(9), NOW)?;
+        for tile_index in 1..=8 {
+            game.guess(RED_GUESSER, Guess { tile_index }, NOW)?;
+            assert!(!game.is_over());
+        }
+        game.guess(RED_GUESSER, Guess { tile_index: 9 }, NOW)?;
+        assert_eq!(
+            game.result,
+            Some(GameResult {
+                winner: TeamColour::Red,
+                reason: GameOverReason::AllAgentsFound,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_team_forfeits_then_other_team_wins_and_game_cannot_continue() -> Result<()> {
+        let mut game = started_game();
+        game.forfeit(BLUE_GUESSER)?;
+        assert_eq!(
+            game.result,
+            Some(GameResult {
+                winner: TeamColour::Red,
+                reason: GameOverReason::Forfeit,
+            })
+        );
+        assert!(matches!(
+            game.provide_clue(RED_SPY_MASTER, clue(1), NOW),
+            Err(CodeNamesError::GameOverError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_asks_for_rematch_then_new_round_starts_and_series_is_scored() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW)?;
+        game.forfeit(RED_GUESSER)?;
+
+        game.rematch(
+            BLUE_SPY_MASTER,
+            random_tiles(),
+            1,
+            TeamColour::Blue,
+            false,
+            NOW,
+        )?;
+
+        assert_eq!(game.series, SeriesScore { blue: 1, red: 0 });
+        assert_eq!(game.tiles, random_tiles());
+        assert_eq!(game.seed, 1);
+        assert_eq!(game.team_turn, TeamColour::Blue);
+        assert_eq!(game.next_action, Action::Clue);
+        assert!(game.history.is_empty());
+        assert!(game.is_started && !game.is_over());
+        for (player_id, group) in PLAYERS {
+            assert_eq!(game.player(player_id).unwrap().group, group);
+        }
+        game.provide_clue(BLUE_SPY_MASTER, clue(1), NOW)?;
+        Ok(())
+    }
+
+    #[test]
+    fn when_rematch_starts_then_swap_requests_from_last_game_are_dropped() -> Result<()> {
+        let mut game = started_game();
+        game.request_swap(BLUE_GUESSER, Group::RedGuessers)?;
+        game.forfeit(RED_GUESSER)?;
+
+        game.rematch(
+            BLUE_SPY_MASTER,
+            random_tiles(),
+            1,
+            TeamColour::Blue,
+            false,
+            NOW,
+        )?;
+
+        assert!(game.pending_swaps.is_empty());
+        assert_eq!(
+            game.player(BLUE_GUESSER).unwrap().group,
+            Group::BlueGuessers
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_rematch_is_not_allowed_then_it_is_rejected() -> Result<()> {
+        let mut game = started_game();
+        assert!(matches!(
+            game.rematch(
+                BLUE_SPY_MASTER,
+                random_tiles(),
+                1,
+                TeamColour::Red,
+                false,
+                NOW
+            ),
+            Err(CodeNamesError::GameNotOverError)
+        ));
+        game.forfeit(RED_GUESSER)?;
+        assert!(matches!(
+            game.rematch(RED_GUESSER, random_tiles(), 1, TeamColour::Red, false, NOW),
+            Err(CodeNamesError::NotHostError)
+        ));
+        assert_eq!(game.series, SeriesScore::default());
+        Ok(())
+    }
+
+    #[test]
+    fn when_rematch_rotates_spy_masters_then_each_team_has_a_new_one() -> Result<()> {
+        let mut game = started_game();
+        game.forfeit(RED_GUESSER)?;
+
+        game.rematch(
+            BLUE_SPY_MASTER,
+            random_tiles(),
+            1,
+            TeamColour::Red,
+            true,
+            NOW,
+        )?;
+
+        assert_eq!(
+            game.player(BLUE_SPY_MASTER).unwrap().group,
+            Group::BlueGuessers
+        );
+        assert_eq!(
+            game.player(BLUE_GUESSER).unwrap().group,
+            Group::BlueSpyMasters
+        );
+        assert_eq!(
+            game.player(RED_SPY_MASTER).unwrap().group,
+            Group::RedGuessers
+        );
+        assert_eq!(
+            game.player(RED_GUESSER).unwrap().group,
+            Group::RedSpyMasters
+        );
+        game.provide_clue(RED_GUESSER, clue(1), NOW)?;
+        Ok(())
+    }
+
+    #[test]
+    fn when_tile_already_revealed_then_guess_is_rejected() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(3), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW)?;
+        assert!(matches!(
+            game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW),
+            Err(CodeNamesError::TileAlreadyRevealedError { tile_index: 1 })
+        ));
+        assert_eq!(game.history.len(), 2);
+        assert_eq!(game.revealed_tiles(), HashSet::from([1]));
+        Ok(())
+    }
+
+    #[test]
+    fn when_guesser_ends_turn_then_other_team_gives_clue() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(3), NOW)?;
+        assert!(matches!(
+            game.end_turn(RED_GUESSER, NOW),
+            Err(CodeNamesError::NoGuessesMadeError)
+        ));
+        game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW)?;
+        game.end_turn(RED_GUESSER, NOW)?;
+        assert_eq!(game.team_turn, TeamColour::Blue);
+        assert_eq!(game.next_action, Action::Clue);
+        assert!(matches!(
+            game.history.last(),
+            Some(GameEvent::EndTurn(EndTurn { team: TeamColour::Red, player_name }))
+                if player_name == "red_guesser_name"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_starts_game_without_enough_players_then_it_is_rejected() -> Result<()> {
+        let mut game = Game::new(
+            random_tiles(),
+            0,
+            BLUE_SPY_MASTER.to_string(),
+            TeamColour::Red,
+        );
+        for (player_id, group) in PLAYERS.into_iter().take(3) {
+            game.add_player(player_id, player(player_id))?;
+            game.move_player(player_id, group)?;
+        }
+        assert!(matches!(
+            game.start(BLUE_SPY_MASTER, NOW),
+            Err(CodeNamesError::NotEnoughPlayersError)
+        ));
+        assert!(!game.is_started);
+        Ok(())
+    }
+
+    #[test]
+    fn when_non_host_starts_game_then_it_is_rejected() -> Result<()> {
+        let mut game = Game::new(
+            random_tiles(),
+            0,
+            BLUE_SPY_MASTER.to_string(),
+            TeamColour::Red,
+        );
+        for (player_id, group) in PLAYERS {
+            game.add_player(player_id, player(player_id))?;
+            game.move_player(player_id, group)?;
+        }
+        assert!(matches!(
+            game.start(RED_SPY_MASTER, NOW),
+            Err(CodeNamesError::NotHostError)
+        ));
+        assert!(!game.is_started);
+        Ok(())
+    }
+
+    #[test]
+    fn when_game_already_started_then_it_cannot_be_started_again() {
+        let mut game = started_game();
+        assert!(matches!(
+            game.start(BLUE_SPY_MASTER, NOW),
+            Err(CodeNamesError::GameAlreadyStartedError)
+        ));
+    }
+
+    #[test]
+    fn when_game_not_started_then_moves_are_rejected() -> Result<()> {
+        let mut game = Game::new(
+            random_tiles(),
+            0,
+            BLUE_SPY_MASTER.to_string(),
+            TeamColour::Red,
+        );
+        for (player_id, group) in PLAYERS {
+            game.add_player(player_id, player(player_id))?;
+            game.move_player(player_id, group)?;
+        }
+        assert!(matches!(
+            game.provide_clue(RED_SPY_MASTER, clue(1), NOW),
+            Err(CodeNamesError::GameNotStartedError)
+        ));
+        assert!(matches!(
+            game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW),
+            Err(CodeNamesError::GameNotStartedError)
+        ));
+        assert!(matches!(
+            game.end_turn(RED_GUESSER, NOW),
+            Err(CodeNamesError::GameNotStartedError)
+        ));
+        assert!(matches!(
+            game.forfeit(RED_GUESSER),
+            Err(CodeNamesError::GameNotStartedError)
+        ));
+        assert!(game.history.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn when_player_moves_before_start_then_they_join_the_new_group() -> Result<()> {
+        let mut game = Game::new(
+            random_tiles(),
+            0,
+            BLUE_SPY_MASTER.to_string(),
+            TeamColour::Red,
+        );
+        game.add_player(RED_GUESSER, player(RED_GUESSER))?;
+        game.move_player(RED_GUESSER, Group::BlueGuessers)?;
+        game.move_player(RED_GUESSER, Group::RedGuessers)?;
+        let public_id = game.public_id(RED_GUESSER)?.to_string();
+        assert!(game.teams.red.guessers.contains_key(&public_id));
+        assert!(game.teams.blue.guessers.is_empty());
+        assert!(game.teams.spectators.is_empty());
+        assert_eq!(
+            game.player(RED_GUESSER).map(|player| player.group.clone()),
+            Some(Group::RedGuessers)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_player_moves_after_start_then_it_is_rejected() -> Result<()> {
+        let mut game = started_game();
+        assert!(matches!(
+            game.move_player(BLUE_GUESSER, Group::RedSpyMasters),
+            Err(CodeNamesError::GameAlreadyStartedError)
+        ));
+        assert_eq!(
+            game.player(BLUE_GUESSER).unwrap().group,
+            Group::BlueGuessers
+        );
+        game.forfeit(BLUE_GUESSER)?;
+        // Once the board is revealed, players can rearrange themselves for a rematch.
+        game.move_player(BLUE_GUESSER, Group::RedSpyMasters)?;
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_approves_swap_then_player_acts_for_their_new_group() -> Result<()> {
+        let mut game = started_game();
+        game.add_player(SPECTATOR, player(SPECTATOR))?;
+        let spectator = game.public_id(SPECTATOR)?.to_string();
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW)?;
+
+        game.request_swap(SPECTATOR, Group::RedGuessers)?;
+        assert_eq!(
+            game.pending_swaps.get(&spectator),
+            Some(&Group::RedGuessers)
+        );
+        assert!(matches!(
+            game.approve_swap(RED_GUESSER, spectator.as_str()),
+            Err(CodeNamesError::NotHostError)
+        ));
+        game.approve_swap(BLUE_SPY_MASTER, spectator.as_str())?;
+
+        assert!(game.pending_swaps.is_empty());
+        assert!(matches!(
+            game.history.last(),
+            Some(GameEvent::Swap(Swap {
+                from: Group::Spectators,
+                to: Group::RedGuessers,
+                ..
+            }))
+        ));
+        // The swap doesn't count as a move, so the turn can still be ended.
+        game.end_turn(SPECTATOR, NOW)?;
+        Ok(())
+    }
+
+    #[test]
+    fn when_swap_would_leave_a_team_without_a_spy_master_then_it_is_rejected() -> Result<()> {
+        let mut game = started_game();
+        let red_spy_master = game.public_id(RED_SPY_MASTER)?.to_string();
+        game.request_swap(RED_SPY_MASTER, Group::RedGuessers)?;
+
+        assert!(matches!(
+            game.approve_swap(BLUE_SPY_MASTER, red_spy_master.as_str()),
+            Err(CodeNamesError::NotEnoughPlayersError)
+        ));
+
+        assert_eq!(
+            game.player(RED_SPY_MASTER).unwrap().group,
+            Group::RedSpyMasters
+        );
+        assert!(game.history.is_empty());
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+
+        game.forfeit(RED_GUESSER)?;
+        assert!(matches!(
+            game.approve_swap(BLUE_SPY_MASTER, red_spy_master.as_str()),
+            Err(CodeNamesError::GameOverError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_denies_swap_then_player_stays_in_their_group() -> Result<()> {
+        let mut game = started_game();
+        let blue_guesser = game.public_id(BLUE_GUESSER)?.to_string();
+        assert!(matches!(
+            game.request_swap(BLUE_GUESSER, Group::BlueGuessers),
+            Err(CodeNamesError::AlreadyInGroupError)
+        ));
+        game.request_swap(BLUE_GUESSER, Group::BlueSpyMasters)?;
+
+        game.deny_swap(BLUE_SPY_MASTER, blue_guesser.as_str())?;
+
+        assert!(game.pending_swaps.is_empty());
+        assert_eq!(
+            game.player(BLUE_GUESSER).unwrap().group,
+            Group::BlueGuessers
+        );
+        assert!(matches!(
+            game.approve_swap(BLUE_SPY_MASTER, blue_guesser.as_str()),
+            Err(CodeNamesError::NoSuchSwapRequestError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_clue_names_a_covered_board_word_then_it_is_rejected() -> Result<()> {
+        let mut game = started_game();
+        let board_clue = |word: &str| Clue {
+            word: word.to_string(),
+            count: ClueCount::Number(1),
+        };
+        assert!(matches!(
+            game.provide_clue(RED_SPY_MASTER, board_clue("WORD_1"), NOW),
+            Err(CodeNamesError::ClueIsBoardWordError { word }) if word == "word_1"
+        ));
+        assert!(game.history.is_empty());
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 20 }, NOW)?;
+
+        // The guessed tile is covered, so its word can be used again.
+        game.provide_clue(BLUE_SPY_MASTER, board_clue(" word_1  "), NOW)?;
+        assert!(matches!(
+            game.history.last(),
+            Some(GameEvent::Clue(Clue { word, .. })) if word == "word_1"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_clue_given_then_team_guesses() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        assert_eq!(game.team_turn, TeamColour::Red);
+        assert_eq!(game.next_action, Action::Guess);
+        assert!(matches!(
+            game.provide_clue(RED_SPY_MASTER, clue(2), NOW),
+            Err(CodeNamesError::InvalidActionError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_clue_is_zero_or_unlimited_then_guesses_are_not_limited() -> Result<()> {
+        for count in [ClueCount::Zero, ClueCount::Unlimited] {
+            let mut game = started_game();
+            let clue = Clue {
+                word: "clue".to_string(),
+                count,
+            };
+            game.provide_clue(RED_SPY_MASTER, clue, NOW)?;
+            for tile_index in 1..=8 {
+                game.guess(RED_GUESSER, Guess { tile_index }, NOW)?;
+                assert_eq!(game.team_turn, TeamColour::Red);
+                assert_eq!(game.next_action, Action::Guess);
+            }
+            game.end_turn(RED_GUESSER, NOW)?;
+            assert_eq!(game.team_turn, TeamColour::Blue);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_turn_runs_out_of_time_then_other_team_gives_clue() -> Result<()> {
+        let mut game = started_game();
+        game.settings.clue_time_limit_secs = NonZeroU32::new(30);
+        game.settings.guess_time_limit_secs = NonZeroU32::new(60);
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+        let deadline = game.deadline.unwrap();
+        assert_eq!(deadline, NOW + 60_000);
+
+        assert!(matches!(
+            game.expire_turn(deadline - 1),
+            Err(CodeNamesError::DeadlineNotReachedError)
+        ));
+        game.expire_turn(deadline)?;
+
+        assert!(matches!(
+            game.history.last(),
+            Some(GameEvent::Timeout(Timeout {
+                team: TeamColour::Red,
+                action: Action::Guess
+            }))
+        ));
+        assert_eq!(game.team_turn, TeamColour::Blue);
+        assert_eq!(game.next_action, Action::Clue);
+        let deadline = game.deadline.unwrap();
+        assert_eq!(deadline, NOW + 60_000 + 30_000);
+        game.forfeit(BLUE_GUESSER)?;
+        assert_eq!(game.deadline, None);
+        assert!(matches!(
+            game.expire_turn(deadline),
+            Err(CodeNamesError::DeadlineNotReachedError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_game_is_paused_then_clock_stops_and_moves_are_rejected() -> Result<()> {
+        let mut game = started_game();
+        game.settings.guess_time_limit_secs = NonZeroU32::new(60);
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        let deadline = game.deadline.unwrap();
+        assert_eq!(deadline, NOW + 60_000);
+        assert!(matches!(
+            game.pause(RED_GUESSER, deadline - 10_000),
+            Err(CodeNamesError::NotHostError)
+        ));
+        game.pause(BLUE_SPY_MASTER, deadline - 10_000)?;
+
+        assert!(matches!(
+            game.guess(RED_GUESSER, Guess { tile_index: 1 }, NOW),
+            Err(CodeNamesError::GamePausedError)
+        ));
+        assert!(matches!(
+            game.expire_turn(deadline),
+            Err(CodeNamesError::GamePausedError)
+        ));
+        assert!(matches!(
+            game.pause(BLUE_SPY_MASTER, deadline),
+            Err(CodeNamesError::GamePausedError)
+        ));
+
+        // The team gets back the 10 seconds they had left.
+        game.resume(BLUE_SPY_MASTER, deadline + 5_000)?;
+        assert_eq!(game.deadline, Some(deadline + 15_000));
+        assert!(matches!(
+            &game.history[game.history.len() - 2..],
+            [GameEvent::Pause(Pause { player_name: paused_by }), GameEvent::Resume(Resume { player_name: resumed_by })]
+                if paused_by == "blue_spy_master_name" && resumed_by == "blue_spy_master_name"
+        ));
+        assert!(matches!(
+            game.resume(BLUE_SPY_MASTER, deadline),
+            Err(CodeNamesError::GameNotPausedError)
+        ));
+        game.guess(RED_GUESSER, Guess { tile_index: 1 }, deadline + 5_000)?;
+        assert_eq!(game.next_action, Action::Guess);
+        Ok(())
+    }
+
+    #[test]
+    fn when_guesses_are_correct_then_team_gets_one_bonus_guess() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        for tile_index in 1..=2 {
+            game.guess(RED_GUESSER, Guess { tile_index }, NOW)?;
+            assert_eq!(game.team_turn, TeamColour::Red);
+            assert_eq!(game.next_action, Action::Guess);
+        }
+        game.guess(RED_GUESSER, Guess { tile_index: 3 }, NOW)?;
+        assert_eq!(game.team_turn, TeamColour::Blue);
+        assert_eq!(game.next_action, Action::Clue);
+        Ok(())
+    }
+
+    #[test]
+    fn when_other_teams_tile_guessed_then_turn_ends() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 10 }, NOW)?;
+        assert!(!game.is_over());
+        assert_eq!(game.team_turn, TeamColour::Blue);
+        assert_eq!(game.next_action, Action::Clue);
+        Ok(())
+    }
+
+    #[test]
+    fn when_neutral_tile_guessed_then_turn_ends() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(2), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 20 }, NOW)?;
+        assert!(!game.is_over());
+        assert_eq!(game.team_turn, TeamColour::Blue);
+        assert_eq!(game.next_action, Action::Clue);
+        Ok(())
+    }
+
+    #[test]
+    fn when_last_agent_of_other_team_guessed_then_other_team_wins() -> Result<()> {
+        let mut game = started_game();
+        game.history
+            .extend((10..=16).map(|tile_index| GameEvent::Guess(Guess { tile_index })));
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+        game.guess(RED_GUESSER, Guess { tile_index: 17 }, NOW)?;
+        assert_eq!(
+            game.result,
+            Some(GameResult {
+                winner: TeamColour::Blue,
+                reason: GameOverReason::AllAgentsFound,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_tile_index_out_of_bounds_then_guess_is_rejected() -> Result<()> {
+        let mut game = started_game();
+        game.provide_clue(RED_SPY_MASTER, clue(1), NOW)?;
+        assert!(matches!(
+            game.guess(RED_GUESSER, Guess { tile_index: 25 }, NOW),
+            Err(CodeNamesError::TileIndexOutOfBoundsError { tile_index: 25 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_players_join_then_teams_do_not_contain_their_secret_ids() -> Result<()> {
+        let game = started_game();
+
+        let teams = serde_json::to_string(&game.teams).unwrap();
+        for player_id in [BLUE_SPY_MASTER, BLUE_GUESSER, RED_SPY_MASTER, RED_GUESSER] {
+            assert!(!teams.contains(&format!("\"{}\"", player_id)));
+        }
+        let public_id = game.public_id(RED_GUESSER)?;
+        assert!(game.teams.red.guessers.contains_key(public_id));
+        assert_eq!(
+            game.player(RED_GUESSER).map(|player| player.group.clone()),
+            Some(Group::RedGuessers)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_player_removed_then_public_id_is_forgotten() -> Result<()> {
+        let mut game = started_game();
+
+        game.remove_player(RED_GUESSER)?;
+
+        assert!(!game.player_exists(RED_GUESSER));
+        assert!(matches!(
+            game.public_id(RED_GUESSER),
+            Err(CodeNamesError::NoSuchPlayerError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_leaves_then_another_player_becomes_host() -> Result<()> {
+        let mut game = started_game();
+
+        game.remove_player(BLUE_SPY_MASTER)?;
+
+        let new_host = game
+            .public_ids
+            .iter()
+            .min_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(player_id, _)| player_id.clone())
+            .unwrap();
+        assert_eq!(game.host_id, new_host);
+        assert_host(&game, new_host.as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_transfers_host_then_only_new_host_is_host() -> Result<()> {
+        let mut game = started_game();
+        let red_guesser = game.public_id(RED_GUESSER)?.to_string();
+
+        game.transfer_host(BLUE_SPY_MASTER, red_guesser.as_str())?;
+
+        assert_eq!(game.host_id, RED_GUESSER);
+        assert_host(&game, RED_GUESSER);
+        assert!(matches!(
+            game.transfer_host(BLUE_SPY_MASTER, red_guesser.as_str()),
+            Err(CodeNamesError::NotHostError)
+        ));
+        assert!(matches!(
+            game.transfer_host(RED_GUESSER, red_guesser.as_str()),
+            Err(CodeNamesError::AlreadyHostError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_host_kicks_player_then_they_leave_the_game() -> Result<()> {
+        let mut game = started_game();
+        let red_guesser = game.public_id(RED_GUESSER)?.to_string();
+        let host = game.public_id(BLUE_SPY_MASTER)?.to_string();
+
+        assert!(matches!(
+            game.kick_player(RED_SPY_MASTER, red_guesser.as_str()),
+            Err(CodeNamesError::NotHostError)
+        ));
+        assert!(matches!(
+            game.kick_player(BLUE_SPY_MASTER, host.as_str()),
+            Err(CodeNamesError::CannotKickHostError)
+        ));
+        game.kick_player(BLUE_SPY_MASTER, red_guesser.as_str())?;
+
+        assert!(!game.player_exists(RED_GUESSER));
+        assert!(matches!(
+            game.kick_player(BLUE_SPY_MASTER, red_guesser.as_str()),
+            Err(CodeNamesError::NoSuchPlayerError)
+        ));
+        assert!(matches!(
+            game.add_player(RED_GUESSER, player(RED_GUESSER)),
+            Err(CodeNamesError::PlayerKickedError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_player_acts_then_only_acting_group_is_allowed() -> Result<()> {
+        let groups = [
+            (BLUE_SPY_MASTER, Group::BlueSpyMasters),
+            (BLUE_GUESSER, Group::BlueGuessers),
+            (RED_SPY_MASTER, Group::RedSpyMasters),
+            (RED_GUESSER, Group::RedGuessers),
+            (SPECTATOR, Group::Spectators),
+        ];
+        let turns = [
+            (TeamColour::Blue, Action::Clue, Group::BlueSpyMasters),
+            (TeamColour::Blue, Action::Guess, Group::BlueGuessers),
+            (TeamColour::Red, Action::Clue, Group::RedSpyMasters),
+            (TeamColour::Red, Action::Guess, Group::RedGuessers),
+        ];
+        for (team, action, exp_group) in turns {
+            for (player_id, act_group) in groups.clone() {
+                let mut game = started_game();
+                game.add_player(SPECTATOR, player(SPECTATOR))?;
+                game.team_turn = team.clone();
+                game.next_action = action.clone();
+                assert_eq!(game.acting_group(), exp_group);
+
+                let result = match action {
+                    Action::Clue => game.provide_clue(player_id, clue(1), NOW),
+                    Action::Guess => game.guess(player_id, Guess { tile_index: 20 }, NOW),
+                };
+
+                match result {
+                    Ok(()) => assert_eq!(act_group, exp_group),
+                    Err(CodeNamesError::IllegalPlayerGroupError {
+                        exp_group: err_exp_group,
+                        act_group: err_act_group,
+                    }) => {
+                        assert_ne!(act_group, exp_group);
+                        assert_eq!(err_exp_group, exp_group);
+                        assert_eq!(err_act_group, act_group);
+                    }
+                    Err(err) => panic!("Unexpected error for {player_id}: {err}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_unknown_player_acts_then_no_such_player() {
+        let mut game = started_game();
+
+        let result = game.provide_clue("unknown_player", clue(1), NOW);
+
+        assert!(matches!(result, Err(CodeNamesError::NoSuchPlayerError)));
+    }
+
+    /// A move that one of the players attempts, which may or may not be legal.
+    #[derive(Clone, Debug)]
+    enum Move {
+        Clue(ClueCount),
+        Guess(u8),
+        EndTurn,
+        Forfeit,
+    }
+
+    fn moves() -> impl Strategy<Value = Vec<(usize, Move)>> {
+        let a_move = prop_oneof![
+            3 => prop_oneof![
+                8 => (0..=9u8).prop_map(ClueCount::Number),
+                1 => Just(ClueCount::Zero),
+                1 => Just(ClueCount::Unlimited),
+            ]
+            .prop_map(Move::Clue),
+            8 => (0..25u8).prop_map(Move::Guess),
+            2 => Just(Move::EndTurn),
+            1 => Just(Move::Forfeit),
+        ];
+        proptest::collection::vec((0..PLAYERS.len(), a_move), 0..200)
+    }
+
+    proptest! {
+        #[test]
+        fn when_random_moves_are_made_then_game_stays_consistent(moves in moves()) {
+            let mut game = started_game();
+            for (player_index, a_move) in moves {
+                let (player_id, _) = &PLAYERS[player_index];
+                let before = game.clone();
+                let result = match a_move {
+                    Move::Clue(count) => game.provide_clue(
+                        player_id,
+                        Clue {
+                            word: "clue".to_string(),
+                            count,
+                        }, NOW,
+                    ),
+                    Move::Guess(tile_index) => game.guess(player_id, Guess { tile_index }, NOW),
+                    Move::EndTurn => game.end_turn(player_id, NOW),
+                    Move::Forfeit => game.forfeit(player_id),
+                };
+
+                if result.is_err() {
+                    // Rejected moves leave the game untouched.
+                    prop_assert_eq!(
+                        serde_json::to_string(&game).unwrap(),
+                        serde_json::to_string(&before).unwrap()
+                    );
+                }
+                if before.is_over() {
+                    prop_assert!(result.is_err());
+                }
+                let guesses = game
+                    .history
+                    .iter()
+                    .filter(|event| matches!(event, GameEvent::Guess(_)))
+                    .count();
+                let revealed_tiles = game.revealed_tiles();
+                prop_assert!(revealed_tiles.len() <= 25);
+                // No tile is ever revealed twice.
+                prop_assert_eq!(revealed_tiles.len(), guesses);
+                // Exactly one of the players may act at any time, and they are on the team whose
+                // turn it is.
+                let acting_group = game.acting_group();
+                prop_assert_eq!(
+                    PLAYERS.iter().filter(|(_, group)| *group == acting_group).count(),
+                    1
+                );
+                let is_acting_team = match game.team_turn {
+                    TeamColour::Blue => {
+                        matches!(acting_group, Group::BlueSpyMasters | Group::BlueGuessers)
+                    }
+                    TeamColour::Red => {
+                        matches!(acting_group, Group::RedSpyMasters | Group::RedGuessers)
+                    }
+                };
+                prop_assert!(is_acting_team);
+                prop_assert!(guesses_since_last_clue(&game) <= max_guesses(&game));
+            }
+        }
+    }
+
+    fn guesses_since_last_clue(game: &Game) -> usize {
+        game.history
+            .iter()
+            .rev()
+            .take_while(|event| !matches!(event, GameEvent::Clue(_)))
+            .filter(|event| matches!(event, GameEvent::Guess(_)))
+            .count()
+    }
+
+    fn max_guesses(game: &Game) -> usize {
+        game.history
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                GameEvent::Clue(clue) => Some(clue.count.max_guesses().unwrap_or(25)),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Checks that only the given player is flagged as the host.
+    fn assert_host(game: &Game, host_id: &str) {
+        for player_id in game.public_ids.keys() {
+            assert_eq!(
+                game.player(player_id).unwrap().is_host,
+                player_id == host_id
+            );
+        }
+    }
+
     fn random_tiles() -> [Tile; 25] {
         from_fn(|_| Tile {
             word: "s".to_string(),
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OwnedMutexGuard;
+
+/// Hands out one lock per game, so that updates to the same game are serialised without
+/// unrelated games having to wait for each other.
+///
+/// A game's lock is dropped from the map once nobody holds or waits on it.
+#[derive(Default)]
+pub struct GameLocks {
+    locks: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl GameLocks {
+    pub async fn lock(&self, game_id: &str) -> GameLockGuard {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(game_id.to_string())
+            .or_default()
+            .clone();
+        GameLockGuard {
+            guard: Some(lock.lock_owned().await),
+            game_id: game_id.to_string(),
+            locks: self.locks.clone(),
+        }
+    }
+}
+
+pub struct GameLockGuard {
+    guard: Option<OwnedMutexGuard<()>>,
+    game_id: String,
+    locks: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl Drop for GameLockGuard {
+    fn drop(&mut self) {
+        // Release the game's lock before checking whether anyone else still needs it.
+        drop(self.guard.take());
+        let mut locks = self.locks.lock().unwrap();
+        if let Some(lock) = locks.get(self.game_id.as_str()) {
+            if Arc::strong_count(lock) == 1 {
+                locks.remove(self.game_id.as_str());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_locks::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn when_game_is_locked_then_other_games_can_still_be_locked() {
+        let game_locks = GameLocks::default();
+        let _game_1_guard = game_locks.lock("game_1").await;
+        tokio::time::timeout(Duration::from_secs(1), game_locks.lock("game_2"))
+            .await
+            .expect("Locking a different game should not wait");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), game_locks.lock("game_1"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn when_lock_is_released_then_it_is_removed() {
+        let game_locks = GameLocks::default();
+        drop(game_locks.lock("game_1").await);
+        assert!(game_locks.locks.lock().unwrap().is_empty());
+    }
+}
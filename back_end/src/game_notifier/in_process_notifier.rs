@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+use crate::game::Result;
+
+use super::{GameNotifier, GameUpdate, CHANNEL_CAPACITY};
+
+/// Only notifies subscribers within this server, so is only suitable when running a single server.
+pub struct InProcessGameNotifier {
+    sender: broadcast::Sender<GameUpdate>,
+}
+
+impl Default for InProcessGameNotifier {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+#[async_trait]
+impl GameNotifier for InProcessGameNotifier {
+    async fn publish(&self, game_id: &str) -> Result<()> {
+        // Sending only fails when nobody is subscribed, in which case nobody needs to know.
+        let _ = self.sender.send(GameUpdate::Changed(game_id.to_string()));
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<GameUpdate> {
+        self.sender.subscribe()
+    }
+}
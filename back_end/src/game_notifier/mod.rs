@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+use crate::game::Result;
+
+pub mod in_process_notifier;
+pub mod redis_notifier;
+
+/// Tells every server when a game has changed, so each can push the new state to its own players.
+#[async_trait]
+pub trait GameNotifier: Send + Sync + 'static {
+    async fn publish(&self, game_id: &str) -> Result<()>;
+
+    /// Receives an update for every changed game, including changes published by this server.
+    fn subscribe(&self) -> broadcast::Receiver<GameUpdate>;
+}
+
+/// Tells subscribers which games to push the latest state of.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameUpdate {
+    /// The game with this ID has changed.
+    Changed(String),
+    /// Notifications may have been missed, so any game may have changed.
+    All,
+}
+
+/// How many notifications a slow subscriber may fall behind by before it starts missing them.
+const CHANNEL_CAPACITY: usize = 1024;
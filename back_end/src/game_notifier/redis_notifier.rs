@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use redis::{
+    aio::{ConnectionManager, PubSub},
+    AsyncCommands, Client, IntoConnectionInfo, RedisResult,
+};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::game::Result;
+
+use super::{GameNotifier, GameUpdate, CHANNEL_CAPACITY};
+
+const CHANNEL_NAME: &str = "codenames.game-updates";
+
+/// Notifies every server subscribed to the same Redis, through a pub/sub channel.
+pub struct RedisGameNotifier {
+    connection: ConnectionManager,
+    sender: broadcast::Sender<GameUpdate>,
+}
+
+impl RedisGameNotifier {
+    pub async fn new<T: IntoConnectionInfo>(url: T) -> RedisResult<Self> {
+        let client = Client::open(url)?;
+        let connection = ConnectionManager::new(client.clone()).await?;
+        let pubsub = subscribe(&client).await?;
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        tokio::spawn(forward_messages(client, pubsub, sender.clone()));
+        Ok(Self { connection, sender })
+    }
+}
+
+#[async_trait]
+impl GameNotifier for RedisGameNotifier {
+    async fn publish(&self, game_id: &str) -> Result<()> {
+        Ok(self
+            .connection
+            .clone()
+            .publish(CHANNEL_NAME, game_id)
+            .await?)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<GameUpdate> {
+        self.sender.subscribe()
+    }
+}
+
+async fn subscribe(client: &Client) -> RedisResult<PubSub> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(CHANNEL_NAME).await?;
+    Ok(pubsub)
+}
+
+/// Passes messages from the pub/sub channel on to this server's subscribers, resubscribing
+/// whenever the connection to Redis is lost. Any game may have changed while the connection was
+/// down, so subscribers are told to refresh every game once it is back.
+async fn forward_messages(
+    client: Client,
+    mut pubsub: PubSub,
+    sender: broadcast::Sender<GameUpdate>,
+) {
+    loop {
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            match message.get_payload::<String>() {
+                Ok(game_id) => {
+                    // Sending only fails when nobody is subscribed, in which case nobody needs to know.
+                    let _ = sender.send(GameUpdate::Changed(game_id));
+                }
+                Err(err) => eprintln!("Error reading game update notification: {}", err),
+            }
+        }
+        drop(messages);
+        eprintln!("Lost connection to game update notifications, resubscribing");
+        pubsub = loop {
+            match subscribe(&client).await {
+                Ok(pubsub) => break pubsub,
+                Err(err) => {
+                    eprintln!(
+                        "Failed to resubscribe to game update notifications: {}",
+                        err
+                    );
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        };
+        let _ = sender.send(GameUpdate::All);
+    }
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::game::{Game, Result};
+
+use super::{GameStore, VersionedGame, DEFAULT_GAME_TTL, NEW_GAME_VERSION};
+
+/// Keeps games in the server's memory, so they are lost on restart and not shared between servers.
+///
+/// Expired games are swept away whenever a game is written.
+pub struct InMemoryGameRepository {
+    games: Mutex<HashMap<String, StoredGame>>,
+    ttl: Duration,
+}
+
+struct StoredGame {
+    game: VersionedGame,
+    expires_at: Instant,
+}
+
+impl InMemoryGameRepository {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            games: Default::default(),
+            ttl,
+        }
+    }
+}
+
+impl Default for InMemoryGameRepository {
+    fn default() -> Self {
+        Self::new(DEFAULT_GAME_TTL)
+    }
+}
+
+#[async_trait]
+impl GameStore for InMemoryGameRepository {
+    async fn get(&self, game_id: &str) -> Result<Option<VersionedGame>> {
+        let now = Instant::now();
+        Ok(self
+            .games
+            .lock()
+            .unwrap()
+            .get(game_id)
+            .filter(|stored| stored.expires_at > now)
+            .map(|stored| stored.game.clone()))
+    }
+
+    async fn compare_and_set(&self, game_id: &str, version: u64, game: &Game) -> Result<bool> {
+        let now = Instant::now();
+        let mut games = self.games.lock().unwrap();
+        games.retain(|_, stored| stored.expires_at > now);
+        let current_version = games
+            .get(game_id)
+            .map_or(NEW_GAME_VERSION, |stored| stored.game.version);
+        if current_version != version {
+            return Ok(false);
+        }
+        games.insert(
+            game_id.to_string(),
+            StoredGame {
+                game: VersionedGame {
+                    game: game.clone(),
+                    version: version + 1,
+                },
+                expires_at: now + self.ttl,
+            },
+        );
+        Ok(true)
+    }
+
+    async fn compare_and_del(&self, game_id: &str, version: u64) -> Result<bool> {
+        let now = Instant::now();
+        let mut games = self.games.lock().unwrap();
+        games.retain(|_, stored| stored.expires_at > now);
+        match games.get(game_id) {
+            Some(stored) if stored.game.version == version => {
+                games.remove(game_id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, Result, Tile};
+    use crate::game_repo::in_memory_repo::InMemoryGameRepository;
+    use crate::game_repo::{GameStore, NEW_GAME_VERSION};
+    use common::api::v1::models::{TeamColour, TileColour};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn when_game_not_written_within_ttl_then_it_expires() -> Result<()> {
+        let repo = InMemoryGameRepository::new(Duration::ZERO);
+        assert!(
+            repo.compare_and_set("game_1", NEW_GAME_VERSION, &game())
+                .await?
+        );
+        assert!(repo.get("game_1").await?.is_none());
+        assert!(repo.games.lock().unwrap().contains_key("game_1"));
+        // Writing any game sweeps away the expired ones.
+        assert!(
+            repo.compare_and_set("game_2", NEW_GAME_VERSION, &game())
+                .await?
+        );
+        assert!(!repo.games.lock().unwrap().contains_key("game_1"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn when_game_written_then_its_expiry_is_refreshed() -> Result<()> {
+        let repo = InMemoryGameRepository::new(Duration::from_secs(60));
+        assert!(
+            repo.compare_and_set("game_1", NEW_GAME_VERSION, &game())
+                .await?
+        );
+        let expires_at = repo.games.lock().unwrap()["game_1"].expires_at;
+        assert!(repo.compare_and_set("game_1", 1, &game()).await?);
+        assert!(repo.games.lock().unwrap()["game_1"].expires_at >= expires_at);
+        assert_eq!(repo.get("game_1").await?.map(|game| game.version), Some(2));
+        Ok(())
+    }
+
+    fn game() -> Game {
+        Game::new(
+            std::array::from_fn(|_| Tile {
+                word: "word".to_string(),
+                colour: TileColour::Grey,
+            }),
+            0,
+            "player_1".to_string(),
+            TeamColour::Red,
+        )
+    }
+}
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use std::time::Duration;
+
+use crate::game::{Game, Result};
+
+pub mod in_memory_repo;
+pub mod redis_repo;
+
+/// Storage for games, keyed by game ID.
+///
+/// Every stored game has a version, which is bumped on each write. Writes only succeed if the game
+/// is still at the version the caller read, so that several servers can safely share one store.
+///
+/// Games expire once they have not been written to for the store's time-to-live, so that games
+/// abandoned without every player leaving do not live forever.
+#[async_trait]
+pub trait GameStore: Send + Sync + 'static {
+    async fn get(&self, game_id: &str) -> Result<Option<VersionedGame>>;
+
+    /// Writes the game if it is still at the given version, returning whether it was written.
+    /// A version of [`NEW_GAME_VERSION`] only matches a game that does not exist yet.
+    async fn compare_and_set(&self, game_id: &str, version: u64, game: &Game) -> Result<bool>;
+
+    /// Deletes the game if it is still at the given version, returning whether it was deleted.
+    async fn compare_and_del(&self, game_id: &str, version: u64) -> Result<bool>;
+}
+
+/// How long a game is kept after it was last changed, unless configured otherwise.
+pub const DEFAULT_GAME_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The version that a game which has not been stored yet is at.
+pub const NEW_GAME_VERSION: u64 = 0;
+
+#[derive(Clone, Debug)]
+pub struct VersionedGame {
+    pub game: Game,
+    pub version: u64,
+}
@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use redis::{
+    aio::ConnectionManager, AsyncCommands, Client, IntoConnectionInfo, RedisResult, Script,
+};
+use std::time::Duration;
+
+use crate::game::{CodeNamesError, Game, Result};
+
+use super::{GameStore, VersionedGame};
+
+/// Stores each game in Redis as a hash holding its version and its JSON.
+///
+/// All requests share one multiplexed connection, which reconnects automatically if it drops.
+/// Version checks are done in Lua scripts, so they are atomic with the writes they guard. Each write
+/// also resets the game's expiry.
+pub struct RedisGameRepository {
+    connection: ConnectionManager,
+    ttl: Duration,
+    compare_and_set_script: Script,
+    compare_and_del_script: Script,
+}
+
+const COMPARE_AND_SET_SCRIPT: &str = r"
+local version = redis.call('HGET', KEYS[1], 'version') or '0'
+if version ~= ARGV[1] then
+    return 0
+end
+redis.call('HSET', KEYS[1], 'version', tostring(tonumber(ARGV[1]) + 1), 'game', ARGV[2])
+redis.call('PEXPIRE', KEYS[1], ARGV[3])
+return 1
+";
+
+const COMPARE_AND_DEL_SCRIPT: &str = r"
+if redis.call('HGET', KEYS[1], 'version') ~= ARGV[1] then
+    return 0
+end
+redis.call('DEL', KEYS[1])
+return 1
+";
+
+impl RedisGameRepository {
+    pub async fn new<T: IntoConnectionInfo>(url: T, ttl: Duration) -> RedisResult<Self> {
+        let client = Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            ttl,
+            compare_and_set_script: Script::new(COMPARE_AND_SET_SCRIPT),
+            compare_and_del_script: Script::new(COMPARE_AND_DEL_SCRIPT),
+        })
+    }
+}
+
+#[async_trait]
+impl GameStore for RedisGameRepository {
+    async fn get(&self, game_id: &str) -> Result<Option<VersionedGame>> {
+        let (version, game): (Option<u64>, Option<String>) = self
+            .connection
+            .clone()
+            .hget(game_id, &["version", "game"])
+            .await?;
+        match (version, game) {
+            (Some(version), Some(game)) => Ok(Some(VersionedGame {
+                game: serde_json::from_str(game.as_str())
+                    .map_err(|err| CodeNamesError::StorageError(err.to_string()))?,
+                version,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    async fn compare_and_set(&self, game_id: &str, version: u64, game: &Game) -> Result<bool> {
+        let value = serde_json::to_string(game)
+            .map_err(|err| CodeNamesError::StorageError(err.to_string()))?;
+        Ok(self
+            .compare_and_set_script
+            .key(game_id)
+            .arg(version)
+            .arg(value)
+            .arg(self.ttl.as_millis() as u64)
+            .invoke_async(&mut self.connection.clone())
+            .await?)
+    }
+
+    async fn compare_and_del(&self, game_id: &str, version: u64) -> Result<bool> {
+        Ok(self
+            .compare_and_del_script
+            .key(game_id)
+            .arg(version)
+            .invoke_async(&mut self.connection.clone())
+            .await?)
+    }
+}
+
+impl From<redis::RedisError> for CodeNamesError {
+    fn from(err: redis::RedisError) -> Self {
+        CodeNamesError::StorageError(err.to_string())
+    }
+}
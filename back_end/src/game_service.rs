@@ -1,41 +1,72 @@
-use common::api::v1::models::{Clue, Group, Guess, Player, TeamColour, TileColour};
-use rand::{seq::SliceRandom, Rng};
-use tokio::sync::Mutex;
+use common::api::v1::models::{
+    Clue, GameSettings, Group, Guess, Player, TeamColour, TileColour, WordList, WordPack,
+};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use tokio::sync::broadcast;
 
 use crate::{
-    game::{CodeNamesError, Game, Result, Tile},
-    game_repo::GameRepository,
+    game::{now_millis, CodeNamesError, Game, Result, Tile},
+    game_locks::GameLocks,
+    game_notifier::{GameNotifier, GameUpdate},
+    game_repo::{GameStore, VersionedGame, NEW_GAME_VERSION},
+    word_lists::{WordLists, DEFAULT_WORD_LIST},
 };
 
-const WORDS: &'static [u8; 3283] = include_bytes!("../wordlist-eng.json");
+/// How many times a request is attempted before giving up, when other servers keep modifying the
+/// same game in between reading and writing it.
+const MAX_REQUEST_ATTEMPTS: usize = 5;
 
-pub struct GameService {
-    repo: Mutex<GameRepository>,
+pub struct GameService<S: GameStore, N: GameNotifier> {
+    repo: S,
+    notifier: N,
+    game_locks: GameLocks,
+    word_lists: WordLists,
 }
 
-impl GameService {
-    pub fn new(repo: GameRepository) -> Self {
+impl<S: GameStore, N: GameNotifier> GameService<S, N> {
+    pub fn new(repo: S, notifier: N, word_lists: WordLists) -> Self {
         Self {
-            repo: Mutex::new(repo),
+            repo,
+            notifier,
+            game_locks: Default::default(),
+            word_lists,
         }
     }
 
-    pub async fn new_game(&self, player_id: String) -> String {
-        let mut rng = rand::rngs::OsRng::default();
+    pub async fn new_game(
+        &self,
+        player_id: String,
+        word_list: Option<WordList>,
+        seed: Option<u64>,
+        settings: GameSettings,
+    ) -> Result<String> {
+        let words = self.word_lists.words(word_list.clone())?;
         // TODO: add nicer game id generator.
         let game_id = uuid::Uuid::new_v4().simple().to_string();
-        let first_turn = rng.gen();
-        let game = Game::new(
-            Self::generate_tiles(&mut rng, &first_turn),
-            player_id,
-            first_turn,
-        );
-        self.repo.lock().await.set(game_id.as_str(), &game);
-        game_id
+        let seed = seed.unwrap_or_else(|| rand::rngs::OsRng.gen());
+        let (tiles, first_turn) = Self::deal(seed, words.words.clone());
+        let mut game = Game::new(tiles, seed, player_id, first_turn);
+        game.language = words.language;
+        // Named lists can be loaded again for a rematch, so only the host's own words are kept.
+        game.word_list = match word_list {
+            None => WordList::Named(DEFAULT_WORD_LIST.to_string()),
+            Some(WordList::Named(name)) => WordList::Named(name),
+            Some(WordList::Custom(_)) => WordList::Custom(words.words),
+        };
+        game.settings = settings;
+        if !self
+            .repo
+            .compare_and_set(game_id.as_str(), NEW_GAME_VERSION, &game)
+            .await?
+        {
+            return Err(CodeNamesError::ConcurrentModificationError);
+        }
+        Ok(game_id)
     }
 
     pub async fn start_game(&self, game_id: &str, player_id: &str) -> Result<Game> {
-        self.perform_request(game_id, |game| game.start(player_id))
+        self.perform_request(game_id, |game| game.start(player_id, now_millis()))
             .await
     }
 
@@ -46,7 +77,7 @@ impl GameService {
     }
 
     pub async fn add_player(&self, game_id: &str, player_id: &str, player: Player) -> Result<Game> {
-        self.perform_request(game_id, |game| game.add_player(player_id, player))
+        self.perform_request(game_id, |game| game.add_player(player_id, player.clone()))
             .await
     }
 
@@ -56,46 +87,157 @@ impl GameService {
         player_id: &str,
         new_group: Group,
     ) -> Result<Game> {
-        self.perform_request(game_id, |game| game.move_player(player_id, new_group))
-            .await
+        self.perform_request(game_id, |game| {
+            game.move_player(player_id, new_group.clone())
+        })
+        .await
     }
 
+    /// Removes the player from the game, and removes the game itself once nobody is left in it.
     pub async fn remove_player(&self, game_id: &str, player_id: &str) -> Result<Game> {
-        let game = self
-            .perform_request(game_id, |game| game.remove_player(player_id))
-            .await;
-        if let Ok(game) = game.as_ref() {
-            if game.teams.spectators.is_empty()
-                && game.teams.blue.guessers.is_empty()
-                && game.teams.blue.spy_masters.is_empty()
-                && game.teams.red.guessers.is_empty()
-                && game.teams.red.spy_masters.is_empty()
-            {
-                self.remove_game(game_id).await
-            }
-        }
-        game
+        self.perform_request(game_id, |game| game.remove_player(player_id))
+            .await
     }
 
-    pub async fn provide_clue(&self, game_id: &str, player_id: &str, clue: Clue) -> Result<Game> {
-        self.perform_request(game_id, |game| game.provide_clue(player_id, clue))
+    pub async fn request_swap(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        new_group: Group,
+    ) -> Result<Game> {
+        self.perform_request(game_id, |game| {
+            game.request_swap(player_id, new_group.clone())
+        })
+        .await
+    }
+
+    pub async fn approve_swap(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        swapped_player_id: &str,
+    ) -> Result<Game> {
+        self.perform_request(game_id, |game| {
+            game.approve_swap(player_id, swapped_player_id)
+        })
+        .await
+    }
+
+    pub async fn deny_swap(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        swapped_player_id: &str,
+    ) -> Result<Game> {
+        self.perform_request(game_id, |game| game.deny_swap(player_id, swapped_player_id))
             .await
     }
 
+    pub async fn transfer_host(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        new_host_id: &str,
+    ) -> Result<Game> {
+        self.perform_request(game_id, |game| game.transfer_host(player_id, new_host_id))
+            .await
+    }
+
+    pub async fn kick_player(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        kicked_player_id: &str,
+    ) -> Result<Game> {
+        self.perform_request(game_id, |game| {
+            game.kick_player(player_id, kicked_player_id)
+        })
+        .await
+    }
+
+    pub async fn provide_clue(&self, game_id: &str, player_id: &str, clue: Clue) -> Result<Game> {
+        self.perform_request(game_id, |game| {
+            game.provide_clue(player_id, clue.clone(), now_millis())
+        })
+        .await
+    }
+
     pub async fn guess(&self, game_id: &str, player_id: &str, guess: Guess) -> Result<Game> {
-        self.perform_request(game_id, |game| game.guess(player_id, guess))
+        self.perform_request(game_id, |game| {
+            game.guess(player_id, guess.clone(), now_millis())
+        })
+        .await
+    }
+
+    pub async fn end_turn(&self, game_id: &str, player_id: &str) -> Result<Game> {
+        self.perform_request(game_id, |game| game.end_turn(player_id, now_millis()))
+            .await
+    }
+
+    /// Ends the current turn if it has run out of time.
+    pub async fn expire_turn(&self, game_id: &str) -> Result<Game> {
+        self.perform_request(game_id, |game| game.expire_turn(now_millis()))
+            .await
+    }
+
+    pub async fn pause(&self, game_id: &str, player_id: &str) -> Result<Game> {
+        self.perform_request(game_id, |game| game.pause(player_id, now_millis()))
+            .await
+    }
+
+    pub async fn resume(&self, game_id: &str, player_id: &str) -> Result<Game> {
+        self.perform_request(game_id, |game| game.resume(player_id, now_millis()))
+            .await
+    }
+
+    pub async fn forfeit(&self, game_id: &str, player_id: &str) -> Result<Game> {
+        self.perform_request(game_id, |game| game.forfeit(player_id))
             .await
     }
 
+    /// Deals a new board for a game that is over, from the same words.
+    pub async fn rematch(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        rotate_spy_masters: bool,
+    ) -> Result<Game> {
+        self.perform_request(game_id, |game| {
+            let seed = rand::rngs::OsRng.gen();
+            let words = self.word_lists.words(Some(game.word_list.clone()))?;
+            let (tiles, first_turn) = Self::deal(seed, words.words);
+            game.rematch(
+                player_id,
+                tiles,
+                seed,
+                first_turn,
+                rotate_spy_masters,
+                now_millis(),
+            )
+        })
+        .await
+    }
+
     pub async fn get_game(&self, game_id: &str) -> Result<Game> {
-        self.repo
-            .lock()
+        self.get_versioned_game(game_id)
             .await
-            .get(game_id)
-            .ok_or(CodeNamesError::NoSuchGameError)
+            .map(|versioned| versioned.game)
+    }
+
+    /// Generates the tiles and picks the team that goes first, always the same way for the same
+    /// seed and words.
+    fn deal(seed: u64, words: Vec<String>) -> ([Tile; 25], TeamColour) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let first_turn = rng.gen();
+        let tiles = Self::generate_tiles(&mut rng, &first_turn, words);
+        (tiles, first_turn)
     }
 
-    fn generate_tiles<R: Rng>(rng: &mut R, first_turn: &TeamColour) -> [Tile; 25] {
+    fn generate_tiles<R: Rng>(
+        rng: &mut R,
+        first_turn: &TeamColour,
+        mut words: Vec<String>,
+    ) -> [Tile; 25] {
         let mut tile_colours: [TileColour; 25] = [
             TileColour::Black,
             TileColour::Blue,
@@ -127,26 +269,258 @@ impl GameService {
             TileColour::Grey,
         ];
         tile_colours.shuffle(rng);
-        let mut words: Vec<&'static str> = serde_json::from_slice(WORDS).unwrap();
         words.shuffle(rng);
         std::array::from_fn(|index| Tile {
-            word: words.get(index).unwrap().to_string(),
+            word: words[index].clone(),
             colour: tile_colours.get(index).unwrap().clone(),
         })
     }
 
-    async fn remove_game(&self, game_id: &str) {
-        self.repo.lock().await.del(game_id);
+    /// Describes the word lists that new games can choose from.
+    pub fn word_packs(&self) -> Vec<WordPack> {
+        self.word_lists.packs()
+    }
+
+    /// Receives the ID of every game that is changed, by any server.
+    pub fn subscribe_to_updates(&self) -> broadcast::Receiver<GameUpdate> {
+        self.notifier.subscribe()
     }
 
-    async fn perform_request<F>(&self, game_id: &str, f: F) -> Result<Game>
+    async fn get_versioned_game(&self, game_id: &str) -> Result<VersionedGame> {
+        self.repo
+            .get(game_id)
+            .await?
+            .ok_or(CodeNamesError::NoSuchGameError)
+    }
+
+    /// Applies the request to the latest version of the game and stores the result.
+    ///
+    /// If the game was changed by someone else in the meantime, the request is re-applied to the
+    /// newer version, so `f` may be called more than once.
+    async fn perform_request<F>(&self, game_id: &str, mut f: F) -> Result<Game>
     where
-        F: FnOnce(&mut Game) -> Result<()>,
+        F: FnMut(&mut Game) -> Result<()>,
     {
-        let mut repo = self.repo.lock().await;
-        let mut game = repo.get(game_id).ok_or(CodeNamesError::NoSuchGameError)?;
-        f(&mut game)?;
-        repo.set(game_id, &game);
-        Ok(game.clone())
+        // Requests handled by this server don't need to race each other.
+        let _lock = self.game_locks.lock(game_id).await;
+        for _ in 0..MAX_REQUEST_ATTEMPTS {
+            let VersionedGame { mut game, version } = self.get_versioned_game(game_id).await?;
+            f(&mut game)?;
+            let written = if game.has_players() {
+                self.repo.compare_and_set(game_id, version, &game).await?
+            } else {
+                self.repo.compare_and_del(game_id, version).await?
+            };
+            if written {
+                if let Err(err) = self.notifier.publish(game_id).await {
+                    eprintln!("Failed to publish update to game {}: {}", game_id, err);
+                }
+                return Ok(game);
+            }
+        }
+        Err(CodeNamesError::ConcurrentModificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use common::api::v1::models::{Group, Player, WordList};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::game::{CodeNamesError, Game, Result};
+    use crate::game_notifier::in_process_notifier::InProcessGameNotifier;
+    use crate::game_notifier::GameUpdate;
+    use crate::game_repo::in_memory_repo::InMemoryGameRepository;
+    use crate::game_repo::{GameStore, VersionedGame};
+    use crate::game_service::{GameService, MAX_REQUEST_ATTEMPTS};
+    use crate::word_lists::{WordLists, DEFAULT_WORD_LIST};
+
+    /// Simulates another server writing to the game straight after each of the next `conflicts`
+    /// reads.
+    #[derive(Default)]
+    struct ConflictingRepository {
+        repo: InMemoryGameRepository,
+        conflicts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl GameStore for ConflictingRepository {
+        async fn get(&self, game_id: &str) -> Result<Option<VersionedGame>> {
+            let game = self.repo.get(game_id).await?;
+            if let Some(VersionedGame { game, version }) = game.as_ref() {
+                if self.conflicts.load(Ordering::SeqCst) > 0 {
+                    self.conflicts.fetch_sub(1, Ordering::SeqCst);
+                    self.repo.compare_and_set(game_id, *version, game).await?;
+                }
+            }
+            Ok(game)
+        }
+
+        async fn compare_and_set(&self, game_id: &str, version: u64, game: &Game) -> Result<bool> {
+            self.repo.compare_and_set(game_id, version, game).await
+        }
+
+        async fn compare_and_del(&self, game_id: &str, version: u64) -> Result<bool> {
+            self.repo.compare_and_del(game_id, version).await
+        }
+    }
+
+    #[tokio::test]
+    async fn when_game_modified_concurrently_then_request_is_retried() -> Result<()> {
+        let game_service = GameService::new(
+            ConflictingRepository::default(),
+            InProcessGameNotifier::default(),
+            WordLists::bundled(),
+        );
+        let game_id = game_service
+            .new_game("player_1".to_string(), None, None, Default::default())
+            .await?;
+        game_service
+            .repo
+            .conflicts
+            .store(MAX_REQUEST_ATTEMPTS - 1, Ordering::SeqCst);
+        let game = game_service
+            .add_player(game_id.as_str(), "player_1", player())
+            .await?;
+        assert!(game.player_exists("player_1"));
+        assert!(
+            game_service
+                .player_exists(game_id.as_str(), "player_1")
+                .await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn when_game_keeps_being_modified_concurrently_then_request_fails() -> Result<()> {
+        let game_service = GameService::new(
+            ConflictingRepository::default(),
+            InProcessGameNotifier::default(),
+            WordLists::bundled(),
+        );
+        let game_id = game_service
+            .new_game("player_1".to_string(), None, None, Default::default())
+            .await?;
+        game_service
+            .repo
+            .conflicts
+            .store(MAX_REQUEST_ATTEMPTS, Ordering::SeqCst);
+        assert!(matches!(
+            game_service
+                .add_player(game_id.as_str(), "player_1", player())
+                .await,
+            Err(CodeNamesError::ConcurrentModificationError)
+        ));
+        assert!(
+            !game_service
+                .player_exists(game_id.as_str(), "player_1")
+                .await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn when_last_player_removed_then_game_is_removed() -> Result<()> {
+        let game_service = GameService::new(
+            InMemoryGameRepository::default(),
+            InProcessGameNotifier::default(),
+            WordLists::bundled(),
+        );
+        let game_id = game_service
+            .new_game("player_1".to_string(), None, None, Default::default())
+            .await?;
+        game_service
+            .add_player(game_id.as_str(), "player_1", player())
+            .await?;
+        game_service
+            .remove_player(game_id.as_str(), "player_1")
+            .await?;
+        assert!(matches!(
+            game_service.get_game(game_id.as_str()).await,
+            Err(CodeNamesError::NoSuchGameError)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn when_game_changes_then_subscribers_are_notified() -> Result<()> {
+        let game_service = GameService::new(
+            InMemoryGameRepository::default(),
+            InProcessGameNotifier::default(),
+            WordLists::bundled(),
+        );
+        let mut updates = game_service.subscribe_to_updates();
+        let game_id = game_service
+            .new_game("player_1".to_string(), None, None, Default::default())
+            .await?;
+        game_service
+            .add_player(game_id.as_str(), "player_1", player())
+            .await?;
+        assert_eq!(updates.try_recv().ok(), Some(GameUpdate::Changed(game_id)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn when_game_uses_a_loaded_word_list_then_only_its_name_is_stored() -> Result<()> {
+        let game_service = GameService::new(
+            InMemoryGameRepository::default(),
+            InProcessGameNotifier::default(),
+            WordLists::bundled(),
+        );
+        let game_id = game_service
+            .new_game("player_1".to_string(), None, None, Default::default())
+            .await?;
+        let game = game_service.get_game(game_id.as_str()).await?;
+        assert_eq!(
+            game.word_list,
+            WordList::Named(DEFAULT_WORD_LIST.to_string())
+        );
+
+        let words: Vec<String> = (0..25).map(|index| format!(" word_{} ", index)).collect();
+        let game_id = game_service
+            .new_game(
+                "player_1".to_string(),
+                Some(WordList::Custom(words)),
+                None,
+                Default::default(),
+            )
+            .await?;
+        let game = game_service.get_game(game_id.as_str()).await?;
+        assert!(matches!(
+            game.word_list,
+            WordList::Custom(words) if words[0] == "word_0"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn when_games_have_the_same_seed_then_they_have_the_same_board() -> Result<()> {
+        let game_service = GameService::new(
+            InMemoryGameRepository::default(),
+            InProcessGameNotifier::default(),
+            WordLists::bundled(),
+        );
+        let mut games = vec![];
+        for seed in [7, 7, 8] {
+            let game_id = game_service
+                .new_game("player_1".to_string(), None, Some(seed), Default::default())
+                .await?;
+            games.push(game_service.get_game(game_id.as_str()).await?);
+        }
+
+        assert_eq!(games[0].seed, 7);
+        assert_eq!(games[0].tiles, games[1].tiles);
+        assert_eq!(games[0].team_turn, games[1].team_turn);
+        assert_ne!(games[0].tiles, games[2].tiles);
+        Ok(())
+    }
+
+    fn player() -> Player {
+        Player {
+            name: "player_1_name".to_string(),
+            group: Group::Spectators,
+            is_host: true,
+        }
     }
 }
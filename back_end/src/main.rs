@@ -1,20 +1,67 @@
 use crate::game_service::GameService;
-use game_repo::GameRepository;
+use game_notifier::{
+    in_process_notifier::InProcessGameNotifier, redis_notifier::RedisGameNotifier, GameNotifier,
+};
+use game_repo::{
+    in_memory_repo::InMemoryGameRepository, redis_repo::RedisGameRepository, GameStore,
+    DEFAULT_GAME_TTL,
+};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use warp::Filter;
+use word_lists::WordLists;
 
 mod api;
+mod clue_validator;
 mod front_end_handler;
 mod game;
+mod game_locks;
+mod game_notifier;
 mod game_repo;
 mod game_service;
+mod word_lists;
 
 #[tokio::main]
 async fn main() {
-    let repo_url = std::env::var("REPO_URL").expect("No REPO_URL env variable");
-    let game_repo: GameRepository = GameRepository::new(repo_url.as_str())
-        .expect(format!("Failed to create repository from URL {}", repo_url).as_str());
-    let game_service: Arc<GameService> = Arc::new(GameService::new(game_repo));
+    // How long a game is kept after its last change.
+    let game_ttl = std::env::var("GAME_TTL_SECS")
+        .ok()
+        .map(|secs| {
+            secs.parse()
+                .map(Duration::from_secs)
+                .unwrap_or_else(|_| panic!("Invalid GAME_TTL_SECS env variable {}", secs))
+        })
+        .unwrap_or(DEFAULT_GAME_TTL);
+    // Games are kept in Redis when a REPO_URL is configured, and in memory otherwise.
+    match std::env::var("REPO_URL") {
+        Ok(repo_url) => {
+            let game_repo = RedisGameRepository::new(repo_url.as_str(), game_ttl)
+                .await
+                .unwrap_or_else(|_| panic!("Failed to create repository from URL {}", repo_url));
+            let game_notifier = RedisGameNotifier::new(repo_url.as_str())
+                .await
+                .unwrap_or_else(|_| panic!("Failed to create notifier from URL {}", repo_url));
+            serve(game_repo, game_notifier).await
+        }
+        Err(_) => {
+            println!("No REPO_URL env variable, so games will be stored in memory");
+            serve(
+                InMemoryGameRepository::new(game_ttl),
+                InProcessGameNotifier::default(),
+            )
+            .await
+        }
+    }
+}
+
+async fn serve<S: GameStore, N: GameNotifier>(game_repo: S, game_notifier: N) {
+    let word_lists_dir =
+        std::env::var("WORD_LISTS_DIR").unwrap_or("./back_end/word_lists".to_string());
+    let word_lists = WordLists::load_dir(Path::new(word_lists_dir.as_str()))
+        .unwrap_or_else(|err| panic!("Failed to load word lists from {}: {}", word_lists_dir, err));
+    let game_service: Arc<GameService<S, N>> =
+        Arc::new(GameService::new(game_repo, game_notifier, word_lists));
     let front_end_static_dir =
         std::env::var("FRONT_END_DIR").unwrap_or("./front_end/dist".to_string());
     let routes = api::health_handler::route()
@@ -0,0 +1,306 @@
+use crate::game::{CodeNamesError, Result};
+use caseless::Caseless;
+use common::api::v1::models::{WordList, WordPack};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+/// The name of the word list used when a new game does not choose one.
+pub const DEFAULT_WORD_LIST: &str = "eng";
+
+/// A board needs a different word for every tile.
+pub const MIN_WORDS: usize = 25;
+
+/// Every game stores the host's own words, so they are limited to keep games small.
+pub const MAX_CUSTOM_WORDS: usize = 1000;
+
+/// Longer words don't fit on a tile.
+pub const MAX_WORD_LENGTH: usize = 20;
+
+/// The word lists that games can draw their boards from, by name.
+pub struct WordLists {
+    lists: HashMap<String, LanguageWords>,
+}
+
+/// The words of a single language that a board can be drawn from.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LanguageWords {
+    /// The language tag of the words, such as `en`. Unknown for words supplied by a host.
+    pub language: Option<String>,
+    pub words: Vec<String>,
+}
+
+impl WordLists {
+    pub fn new(lists: HashMap<String, LanguageWords>) -> Result<Self> {
+        let lists = lists
+            .into_iter()
+            .map(|(name, list)| {
+                let words = validate_words(list.words)?;
+                Ok((
+                    name,
+                    LanguageWords {
+                        language: list.language,
+                        words,
+                    },
+                ))
+            })
+            .collect::<Result<HashMap<String, LanguageWords>>>()?;
+        Ok(Self { lists })
+    }
+
+    /// Loads every `<name>.json` file in the directory as the word list with that name. Each file
+    /// holds a JSON object with the `language` of the list and its `words`.
+    pub fn load_dir(dir: &Path) -> std::result::Result<Self, Box<dyn std::error::Error>> {
+        let mut lists = HashMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| format!("Invalid word list file name {:?}", path))?
+                .to_string();
+            let list: LanguageWords = serde_json::from_slice(&std::fs::read(&path)?)?;
+            lists.insert(name, list);
+        }
+        Ok(Self::new(lists)?)
+    }
+
+    /// The word lists that are shipped with the server.
+    #[cfg(test)]
+    pub fn bundled() -> Self {
+        Self::load_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("word_lists")).unwrap()
+    }
+
+    /// Describes every loaded word list, ordered by name.
+    pub fn packs(&self) -> Vec<WordPack> {
+        let mut packs: Vec<WordPack> = self
+            .lists
+            .iter()
+            .map(|(name, list)| WordPack {
+                name: name.clone(),
+                language: list.language.clone(),
+                word_count: list.words.len(),
+            })
+            .collect();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        packs
+    }
+
+    /// Gets the words for a new game, either from one of the loaded lists, or from the host's own
+    /// words once they are validated.
+    pub fn words(&self, word_list: Option<WordList>) -> Result<LanguageWords> {
+        match word_list {
+            None => self.named(DEFAULT_WORD_LIST),
+            Some(WordList::Named(name)) => self.named(name.as_str()),
+            Some(WordList::Custom(words)) if words.len() > MAX_CUSTOM_WORDS => {
+                Err(CodeNamesError::TooManyWordsError { count: words.len() })
+            }
+            Some(WordList::Custom(words)) => Ok(LanguageWords {
+                language: None,
+                words: validate_words(words)?,
+            }),
+        }
+    }
+
+    fn named(&self, name: &str) -> Result<LanguageWords> {
+        self.lists
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CodeNamesError::NoSuchWordListError {
+                name: name.to_string(),
+            })
+    }
+}
+
+/// Normalizes a word for comparisons, so that words that only differ in case, surrounding
+/// whitespace or how their characters are encoded in Unicode are treated as the same word. Case is
+/// folded the way Unicode's compatibility caseless matching does, so "Straße" and "STRASSE" match.
+pub fn normalize(word: &str) -> String {
+    word.trim()
+        .chars()
+        .nfd()
+        .default_case_fold()
+        .nfkd()
+        .default_case_fold()
+        .nfkc()
+        .collect()
+}
+
+/// Trims each word, and checks that there are enough distinct words of a sensible length.
+fn validate_words(words: Vec<String>) -> Result<Vec<String>> {
+    let mut seen = HashSet::new();
+    let mut validated = Vec::with_capacity(words.len());
+    for word in words {
+        let word: String = word.trim().nfc().collect();
+        if word.is_empty() || word.chars().count() > MAX_WORD_LENGTH {
+            return Err(CodeNamesError::InvalidWordError { word });
+        }
+        if !seen.insert(normalize(word.as_str())) {
+            return Err(CodeNamesError::DuplicateWordError { word });
+        }
+        validated.push(word);
+    }
+    if validated.len() < MIN_WORDS {
+        return Err(CodeNamesError::NotEnoughWordsError {
+            count: validated.len(),
+        });
+    }
+    Ok(validated)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::word_lists::*;
+
+    #[test]
+    fn when_word_lists_dir_loaded_then_default_list_is_available() -> Result<()> {
+        let word_lists = WordLists::bundled();
+
+        let words = word_lists.words(None)?;
+
+        assert_eq!(words.language.as_deref(), Some("en"));
+        assert!(words.words.len() >= MIN_WORDS);
+        assert_eq!(
+            word_lists.words(Some(WordList::Named(DEFAULT_WORD_LIST.to_string())))?,
+            words
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn when_word_list_does_not_exist_then_it_is_rejected() {
+        let word_lists = WordLists::bundled();
+
+        let result = word_lists.words(Some(WordList::Named("klingon".to_string())));
+
+        assert!(matches!(
+            result,
+            Err(CodeNamesError::NoSuchWordListError { name }) if name == "klingon"
+        ));
+    }
+
+    #[test]
+    fn when_custom_words_are_valid_then_they_are_trimmed_and_used() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+        let mut words = custom_words(MIN_WORDS);
+        words[0] = "  spaced ".to_string();
+
+        let words = word_lists.words(Some(WordList::Custom(words)))?;
+
+        assert_eq!(words.language, None);
+        assert_eq!(words.words.len(), MIN_WORDS);
+        assert_eq!(words.words[0], "spaced");
+        Ok(())
+    }
+
+    #[test]
+    fn when_too_few_custom_words_then_they_are_rejected() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+
+        let result = word_lists.words(Some(WordList::Custom(custom_words(MIN_WORDS - 1))));
+
+        assert!(matches!(
+            result,
+            Err(CodeNamesError::NotEnoughWordsError { count }) if count == MIN_WORDS - 1
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_too_many_custom_words_then_they_are_rejected() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+
+        let result = word_lists.words(Some(WordList::Custom(custom_words(MAX_CUSTOM_WORDS + 1))));
+
+        assert!(matches!(
+            result,
+            Err(CodeNamesError::TooManyWordsError { count }) if count == MAX_CUSTOM_WORDS + 1
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_custom_words_repeat_then_they_are_rejected() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+        let mut words = custom_words(MIN_WORDS);
+        words.push("WORD_1".to_string());
+
+        let result = word_lists.words(Some(WordList::Custom(words)));
+
+        assert!(matches!(
+            result,
+            Err(CodeNamesError::DuplicateWordError { word }) if word == "WORD_1"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn when_custom_word_is_empty_or_too_long_then_it_is_rejected() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+        for invalid_word in ["   ".to_string(), "a".repeat(MAX_WORD_LENGTH + 1)] {
+            let mut words = custom_words(MIN_WORDS);
+            words.push(invalid_word);
+
+            let result = word_lists.words(Some(WordList::Custom(words)));
+
+            assert!(matches!(
+                result,
+                Err(CodeNamesError::InvalidWordError { .. })
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn when_word_lists_loaded_then_each_language_is_listed() {
+        let packs = WordLists::bundled().packs();
+
+        let languages: Vec<Option<&str>> =
+            packs.iter().map(|pack| pack.language.as_deref()).collect();
+        assert_eq!(languages, vec![Some("de"), Some("en"), Some("fr")]);
+        assert!(packs.iter().all(|pack| pack.word_count >= MIN_WORDS));
+    }
+
+    #[test]
+    fn when_words_differ_only_in_unicode_encoding_then_they_are_duplicates() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+        let mut words = custom_words(MIN_WORDS);
+        // "CAFÉ" with a precomposed É, and then with an E followed by a combining acute accent.
+        words.push("CAF\u{00C9}".to_string());
+        words.push("cafe\u{0301}".to_string());
+
+        let result = word_lists.words(Some(WordList::Custom(words)));
+
+        assert!(matches!(
+            result,
+            Err(CodeNamesError::DuplicateWordError { word }) if word == "cafe\u{0301}".nfc().collect::<String>()
+        ));
+        assert_eq!(normalize(" Caf\u{00C9} "), normalize("cafe\u{0301}"));
+        Ok(())
+    }
+
+    #[test]
+    fn when_words_differ_only_in_case_folding_then_they_are_duplicates() -> Result<()> {
+        let word_lists = WordLists::new(HashMap::new())?;
+        let mut words = custom_words(MIN_WORDS);
+        words.push("STRASSE".to_string());
+        words.push("Straße".to_string());
+
+        let result = word_lists.words(Some(WordList::Custom(words)));
+
+        assert!(matches!(
+            result,
+            Err(CodeNamesError::DuplicateWordError { word }) if word == "Straße"
+        ));
+        assert_eq!(normalize("Fluß"), normalize("FLUSS"));
+        Ok(())
+    }
+
+    fn custom_words(count: usize) -> Vec<String> {
+        (0..count).map(|index| format!("word_{}", index)).collect()
+    }
+}
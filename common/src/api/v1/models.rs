@@ -1,43 +1,212 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, num::NonZeroU32};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum ClientMessage {
     Heartbeat,
-    EventRequest(EventRequest),
+    EventRequest {
+        /// Optional client-supplied ID, echoed back in the acknowledgement or error for this request.
+        #[serde(default)]
+        request_id: Option<RequestId>,
+        request: EventRequest,
+    },
 }
 
+pub type RequestId = u64;
+
 #[derive(Debug, Deserialize, Serialize)]
 pub enum ServerMessage {
-    StateUpdate(GameView),
+    StateUpdate(Box<GameView>),
+    /// Sent only to the connection whose request was performed, once its state update has been sent.
+    Ack {
+        request_id: RequestId,
+    },
+    Error(ErrorResponse),
+    /// Sent to every connection of a game that has been removed for being idle for too long,
+    /// before the connection is closed.
+    GameExpired,
+    /// Sent to every connection of a player whom the host has kicked, before the connection is
+    /// closed.
+    Kicked,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum EventRequest {
     StartGame,
-    AddPlayer { name: String },
-    MovePlayer { new_group: Group },
+    AddPlayer {
+        name: String,
+    },
+    MovePlayer {
+        new_group: Group,
+    },
     RemovePlayer,
-    Clue { word: String, count: u8 },
-    Guess { tile_index: u8 },
+    Clue {
+        word: String,
+        count: ClueCount,
+    },
+    Guess {
+        tile_index: u8,
+    },
+    EndTurn,
+    Forfeit,
+    /// Deals a new board once the game is over, keeping everyone in their groups.
+    Rematch {
+        rotate_spy_masters: bool,
+    },
+    /// Stops the clock and holds off clues and guesses until the host resumes the game.
+    Pause,
+    Resume,
+    /// Makes another player the host, by their public ID.
+    TransferHost {
+        new_host_id: String,
+    },
+    /// Removes another player from the game, by their public ID.
+    KickPlayer {
+        kicked_id: String,
+    },
+    /// Asks the host to move this player to another group during the game.
+    RequestSwap {
+        new_group: Group,
+    },
+    /// Moves a player who asked for it to their requested group, by their public ID.
+    ApproveSwap {
+        swapped_id: String,
+    },
+    /// Turns down a player's request to move, by their public ID.
+    DenySwap {
+        swapped_id: String,
+    },
+}
+
+/// Sent only to the connection whose request could not be performed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    pub request: EventRequest,
+    pub request_id: Option<RequestId>,
+}
+
+/// Machine-readable error codes. These are part of the API, so existing codes must not be renamed.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    AlreadyHost,
+    AlreadyInGroup,
+    CannotKickHost,
+    ClueCountOutOfBounds,
+    ClueIsBoardWord,
+    CompoundClue,
+    ConcurrentModification,
+    DeadlineNotReached,
+    DuplicateWord,
+    EmptyClue,
+    GameAlreadyStarted,
+    GameNotOver,
+    GameNotPaused,
+    GameNotStarted,
+    GameOver,
+    GamePaused,
+    IllegalPlayerGroup,
+    InvalidAction,
+    InvalidWord,
+    NoGuessesMade,
+    NoSuchGame,
+    NoSuchPlayer,
+    NoSuchSwapRequest,
+    NoSuchWordList,
+    NotEnoughPlayers,
+    NotEnoughWords,
+    NotHost,
+    NotInTeam,
+    PlayerAlreadyInGame,
+    PlayerKicked,
+    Storage,
+    TileAlreadyRevealed,
+    TileIndexOutOfBounds,
+    TooManyWords,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GameView {
     pub is_started: bool,
     pub tiles: [Tile; 25],
+    /// The language of the words on the board, if known.
+    pub language: Option<String>,
+    /// The seed the board was dealt from, which is only shown to the host.
+    pub seed: Option<u64>,
+    pub settings: GameSettings,
+    /// When the current turn ends on its own, in milliseconds since the Unix epoch.
+    pub deadline: Option<u64>,
+    /// When the host paused the game, if it is paused. The deadline is pushed back by the time
+    /// spent paused once the game resumes.
+    pub paused_at: Option<u64>,
+    /// Players, keyed by their public IDs.
     pub teams: Teams,
     pub this_player: Player,
+    /// The public ID of this player, under which they appear in `teams`.
+    pub this_player_id: String,
     pub team_turn: TeamColour,
     pub next_action: Action,
     pub history: Vec<GameEvent>,
+    pub result: Option<GameResult>,
+    pub series: SeriesScore,
+    /// The groups that players have asked the host to move them to, keyed by their public IDs.
+    pub pending_swaps: HashMap<String, Group>,
+}
+
+/// How many games each team has won in this lobby.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SeriesScore {
+    pub blue: u32,
+    pub red: u32,
+}
+
+impl SeriesScore {
+    pub fn record_win(&mut self, winner: &TeamColour) {
+        match winner {
+            TeamColour::Blue => self.blue += 1,
+            TeamColour::Red => self.red += 1,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Clue {
     pub word: String,
-    pub count: u8,
+    pub count: ClueCount,
+}
+
+/// How many tiles a clue refers to.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ClueCount {
+    Number(u8),
+    /// None of the team's tiles relate to the clue, so the guessers may guess as much as they like.
+    Zero,
+    /// Any number of tiles, usually including ones from earlier clues, with no limit on guesses.
+    Unlimited,
+}
+
+impl ClueCount {
+    /// The most guesses the team may make for the clue: one more than the number of tiles, so that
+    /// a tile missed on an earlier turn can be picked up.
+    pub fn max_guesses(&self) -> Option<usize> {
+        match self {
+            ClueCount::Number(count) => Some(*count as usize + 1),
+            ClueCount::Zero | ClueCount::Unlimited => None,
+        }
+    }
+}
+
+impl Display for ClueCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClueCount::Number(count) => write!(f, "{}", count),
+            ClueCount::Zero => write!(f, "0"),
+            ClueCount::Unlimited => write!(f, "∞"),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -45,10 +214,48 @@ pub struct Guess {
     pub tile_index: u8,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EndTurn {
+    pub team: TeamColour,
+    pub player_name: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum GameEvent {
     Clue(Clue),
     Guess(Guess),
+    EndTurn(EndTurn),
+    Swap(Swap),
+    Timeout(Timeout),
+    Pause(Pause),
+    Resume(Resume),
+}
+
+/// A team that ran out of time to give a clue or to guess, which ended their turn.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Timeout {
+    pub team: TeamColour,
+    pub action: Action,
+}
+
+/// The host pausing the game, e.g. while someone steps away.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Pause {
+    pub player_name: String,
+}
+
+/// The host resuming a paused game.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Resume {
+    pub player_name: String,
+}
+
+/// A player who changed groups during the game, with the host's approval.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Swap {
+    pub player_name: String,
+    pub from: Group,
+    pub to: Group,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -85,6 +292,15 @@ impl TeamColour {
     }
 }
 
+impl Display for TeamColour {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TeamColour::Red => write!(f, "Red"),
+            TeamColour::Blue => write!(f, "Blue"),
+        }
+    }
+}
+
 impl rand::distributions::Distribution<TeamColour> for rand::distributions::Standard {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> TeamColour {
         if rng.gen() {
@@ -95,6 +311,32 @@ impl rand::distributions::Distribution<TeamColour> for rand::distributions::Stan
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GameResult {
+    pub winner: TeamColour,
+    pub reason: GameOverReason,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum GameOverReason {
+    /// The losing team guessed the assassin tile.
+    Assassin,
+    /// The winning team uncovered all of its tiles.
+    AllAgentsFound,
+    /// The losing team gave up.
+    Forfeit,
+}
+
+impl Display for GameOverReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameOverReason::Assassin => write!(f, "The assassin was found"),
+            GameOverReason::AllAgentsFound => write!(f, "All agents were found"),
+            GameOverReason::Forfeit => write!(f, "The other team forfeited"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Action {
     Clue,
@@ -108,21 +350,12 @@ pub struct Teams {
     pub spectators: HashMap<String, Player>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Team {
     pub spy_masters: HashMap<String, Player>,
     pub guessers: HashMap<String, Player>,
 }
 
-impl Default for Team {
-    fn default() -> Self {
-        Self {
-            guessers: Default::default(),
-            spy_masters: Default::default(),
-        }
-    }
-}
-
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Player {
     pub name: String,
@@ -146,6 +379,16 @@ pub enum Group {
     RedSpyMasters,
 }
 
+impl Group {
+    pub fn role(&self) -> Role {
+        match self {
+            Group::Spectators => Role::Spectator,
+            Group::BlueGuessers | Group::RedGuessers => Role::Guesser,
+            Group::BlueSpyMasters | Group::RedSpyMasters => Role::SpyMaster,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Tile {
     pub word: String,
@@ -155,6 +398,44 @@ pub struct Tile {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NewGameRequest {
     pub player_name: String,
+    /// The words to draw the board from. The server's default list is used if none is given.
+    #[serde(default)]
+    pub word_list: Option<WordList>,
+    /// Seeds the board, so that the same seed and word list always deal the same board. A random
+    /// seed is used if none is given.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub settings: GameSettings,
+}
+
+/// Rules that the host can change when creating a game.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Whether clues may be made of several words, such as "ice cream".
+    pub allow_compound_clues: bool,
+    /// How long spy masters have to give a clue before their turn ends, if there is a limit.
+    pub clue_time_limit_secs: Option<NonZeroU32>,
+    /// How long guessers have to make their guesses before their turn ends, if there is a limit.
+    pub guess_time_limit_secs: Option<NonZeroU32>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum WordList {
+    /// One of the word lists that the server has loaded, by name.
+    Named(String),
+    /// Words supplied by the host.
+    Custom(Vec<String>),
+}
+
+/// A word list that the server has loaded, which new games can choose by name.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WordPack {
+    pub name: String,
+    /// The language tag of the words, such as `en`.
+    pub language: Option<String>,
+    pub word_count: usize,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
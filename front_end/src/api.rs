@@ -74,9 +74,21 @@ pub fn events(game_id: String, link: &Scope<Game>) -> web_sys::WebSocket {
         .unwrap();
     interval_callback.forget();
 
+    // Re-renders the turn countdown every second.
+    let link_clone = link.clone();
+    let tick_callback = Closure::<dyn Fn()>::new(move || link_clone.send_message(GameMsg::Tick));
+    let tick_interval_id = window
+        .set_interval_with_callback_and_timeout_and_arguments_0(
+            tick_callback.as_ref().unchecked_ref(),
+            1000,
+        )
+        .unwrap();
+    tick_callback.forget();
+
     let onclose_callback = Closure::<dyn FnMut()>::new(move || {
         web_sys::console::log_1(&"connection closed".into());
         window.clear_interval_with_handle(interval_id);
+        window.clear_interval_with_handle(tick_interval_id);
     });
     websocket.set_onclose(Some(onclose_callback.as_ref().unchecked_ref()));
     onclose_callback.forget();
@@ -100,9 +112,14 @@ pub async fn new_game(player_name: String) -> NewGameResponse {
     request_opts
         .method("POST")
         .body(Some(
-            &serde_json::to_string(&NewGameRequest { player_name })
-                .unwrap()
-                .into(),
+            &serde_json::to_string(&NewGameRequest {
+                player_name,
+                word_list: None,
+                seed: None,
+                settings: Default::default(),
+            })
+            .unwrap()
+            .into(),
         ))
         .headers(&request_headers);
     let response: web_sys::Response = wasm_bindgen_futures::JsFuture::from(
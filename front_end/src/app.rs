@@ -12,7 +12,7 @@ pub fn App() -> Html {
             .unwrap()
             .get("game-id")
     }
-    let game_id_handle = use_state_eq(|| get_game_id());
+    let game_id_handle = use_state_eq(get_game_id);
     let joined_handle = use_state_eq(|| true);
     let game_id_handle_clone = game_id_handle.clone();
     let joined_handle_clone = joined_handle.clone();
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
 use common::api::v1::models::{
-    Action, ClientMessage, Clue, EventRequest, GameEvent, GameView, Group, Player, ServerMessage,
-    TeamColour,
+    Action, ClientMessage, Clue, ClueCount, ErrorResponse, EventRequest, GameEvent, GameView,
+    Group, Player, RequestId, ServerMessage, TeamColour,
 };
 use futures::FutureExt;
 use web_sys::HtmlInputElement;
@@ -14,12 +14,18 @@ pub struct Game {
     websocket: Option<web_sys::WebSocket>,
     view: Option<GameView>,
     clue_input: ClueInput,
+    error: Option<ErrorResponse>,
+    next_request_id: RequestId,
+    /// Requests that have been sent but not yet acknowledged or rejected.
+    pending_requests: HashMap<RequestId, EventRequest>,
+    is_expired: bool,
+    is_kicked: bool,
 }
 
 #[derive(Clone, Debug)]
 pub struct ClueInput {
     word: String,
-    count: Option<u8>,
+    count: Option<ClueCount>,
 }
 
 impl Game {
@@ -37,10 +43,14 @@ impl Game {
         let red_guessers_names = concat_player_names(&view.teams.red.guessers);
         let red_spy_masters_names = concat_player_names(&view.teams.red.spy_masters);
 
-        let move_player = ctx.link().callback(|new_group: Group| {
-            GameMsg::SendMessage(ClientMessage::EventRequest(EventRequest::MovePlayer {
-                new_group,
-            }))
+        // Groups are locked while the game is in progress, so players have to ask the host.
+        let is_in_progress = view.is_started && view.result.is_none();
+        let move_player = ctx.link().callback(move |new_group: Group| {
+            if is_in_progress {
+                GameMsg::SendRequest(EventRequest::RequestSwap { new_group })
+            } else {
+                GameMsg::SendRequest(EventRequest::MovePlayer { new_group })
+            }
         });
         let move_player_clone = move_player.clone();
         let join_spectators = move |_| move_player_clone.emit(Group::Spectators);
@@ -53,38 +63,113 @@ impl Game {
         let move_player_clone = move_player.clone();
         let join_red_spy_masters = move |_| move_player_clone.emit(Group::RedSpyMasters);
 
-        let enough_players_to_start = view.teams.blue.spy_masters.len() > 0
-            && view.teams.blue.guessers.len() > 0
-            && view.teams.red.spy_masters.len() > 0
-            && view.teams.red.guessers.len() > 0;
+        let enough_players_to_start = !view.teams.blue.spy_masters.is_empty()
+            && !view.teams.blue.guessers.is_empty()
+            && !view.teams.red.spy_masters.is_empty()
+            && !view.teams.red.guessers.is_empty();
+
+        let start_game = ctx
+            .link()
+            .callback(|()| GameMsg::SendRequest(EventRequest::StartGame));
 
-        let start_game = ctx.link().callback(|()| {
-            GameMsg::SendMessage(ClientMessage::EventRequest(EventRequest::StartGame))
+        let forfeit = ctx
+            .link()
+            .callback(|()| GameMsg::SendRequest(EventRequest::Forfeit));
+
+        let transfer_host = ctx.link().callback(|new_host_id| {
+            GameMsg::SendRequest(EventRequest::TransferHost { new_host_id })
         });
+        let kick_player = ctx
+            .link()
+            .callback(|kicked_id| GameMsg::SendRequest(EventRequest::KickPlayer { kicked_id }));
+        let approve_swap = ctx
+            .link()
+            .callback(|swapped_id| GameMsg::SendRequest(EventRequest::ApproveSwap { swapped_id }));
+        let deny_swap = ctx
+            .link()
+            .callback(|swapped_id| GameMsg::SendRequest(EventRequest::DenySwap { swapped_id }));
+        let mut pending_swaps: Vec<(String, String, Group)> = view
+            .pending_swaps
+            .iter()
+            .filter_map(|(public_id, new_group)| {
+                let player = view
+                    .teams
+                    .spectators
+                    .get(public_id)
+                    .or_else(|| view.teams.blue.guessers.get(public_id))
+                    .or_else(|| view.teams.blue.spy_masters.get(public_id))
+                    .or_else(|| view.teams.red.guessers.get(public_id))
+                    .or_else(|| view.teams.red.spy_masters.get(public_id))?;
+                Some((public_id.clone(), player.name.clone(), new_group.clone()))
+            })
+            .collect();
+        pending_swaps.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+        let has_pending_swap = view.pending_swaps.contains_key(&view.this_player_id);
+
+        let mut other_players: Vec<(&String, &Player)> = view
+            .teams
+            .spectators
+            .iter()
+            .chain(view.teams.blue.guessers.iter())
+            .chain(view.teams.blue.spy_masters.iter())
+            .chain(view.teams.red.guessers.iter())
+            .chain(view.teams.red.spy_masters.iter())
+            .filter(|(public_id, _)| **public_id != view.this_player_id)
+            .collect();
+        other_players.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
 
-        let guess = ctx.link().callback(|tile_index| {
-            GameMsg::SendMessage(ClientMessage::EventRequest(EventRequest::Guess {
-                tile_index,
-            }))
+        let rematch = ctx.link().callback(|rotate_spy_masters| {
+            GameMsg::SendRequest(EventRequest::Rematch { rotate_spy_masters })
         });
+        let rematch_clone = rematch.clone();
+        let pause = ctx
+            .link()
+            .callback(|_| GameMsg::SendRequest(EventRequest::Pause));
+        let resume = ctx
+            .link()
+            .callback(|_| GameMsg::SendRequest(EventRequest::Resume));
+        // Clues and guesses are held off while the game is paused.
+        let is_playing = is_in_progress && view.paused_at.is_none();
+
+        let is_in_team = view.this_player.group != Group::Spectators;
+        let is_current_guesser = view.next_action == Action::Guess
+            && match view.team_turn {
+                TeamColour::Red => view.this_player.group == Group::RedGuessers,
+                TeamColour::Blue => view.this_player.group == Group::BlueGuessers,
+            };
+        let has_guessed = matches!(
+            view.history
+                .iter()
+                .rev()
+                .find(|event| matches!(event, GameEvent::Clue(_) | GameEvent::Guess(_))),
+            Some(GameEvent::Guess(_))
+        );
+
+        let end_turn = ctx
+            .link()
+            .callback(|()| GameMsg::SendRequest(EventRequest::EndTurn));
+
+        let guess = ctx
+            .link()
+            .callback(|tile_index| GameMsg::SendRequest(EventRequest::Guess { tile_index }));
 
         let last_clue: Option<&Clue> = view
             .history
             .iter()
             .filter_map(|event| match event {
                 GameEvent::Clue(clue) => Some(clue),
-                GameEvent::Guess(_) => None,
+                _ => None,
             })
-            .last();
+            .next_back();
 
         let clue_input = self.clue_input.clone();
         let provide_clue = ctx.link().batch_callback(move |_| {
             clue_input.count.map_or(vec![], |count| {
                 vec![
-                    GameMsg::SendMessage(ClientMessage::EventRequest(EventRequest::Clue {
+                    GameMsg::SendRequest(EventRequest::Clue {
                         word: clue_input.word.clone(),
                         count,
-                    })),
+                    }),
                     GameMsg::SetClueInput(ClueInput {
                         word: "".to_string(),
                         count: None,
@@ -92,7 +177,7 @@ impl Game {
                 ]
             })
         });
-        let clue_count = self.clue_input.count.clone();
+        let clue_count = self.clue_input.count;
         let set_clue_word = ctx.link().callback(move |event: InputEvent| {
             let value = event.target_unchecked_into::<HtmlInputElement>().value();
             GameMsg::SetClueInput(ClueInput {
@@ -106,15 +191,34 @@ impl Game {
                 .target_unchecked_into::<HtmlInputElement>()
                 .value()
                 .parse()
-                .ok();
+                .ok()
+                .map(ClueCount::Number);
             GameMsg::SetClueInput(ClueInput {
                 word: clue_word.clone(),
                 count: value,
             })
         });
+        let clue_word = self.clue_input.word.clone();
+        let set_special_clue_count = ctx.link().callback(move |count: ClueCount| {
+            GameMsg::SetClueInput(ClueInput {
+                word: clue_word.clone(),
+                count: Some(count),
+            })
+        });
+        let set_zero_clue_count = set_special_clue_count.clone();
+        let is_clue_count_valid = matches!(
+            self.clue_input.count,
+            Some(ClueCount::Number(1..=9) | ClueCount::Zero | ClueCount::Unlimited)
+        );
 
         html! {
             <div>
+                if let Some(error) = &self.error {
+                    <p><b>{"error: "}</b>{&error.message}</p>
+                }
+                if !self.pending_requests.is_empty() {
+                    <p>{"sending..."}</p>
+                }
                 <h2>{"players"}</h2>
                 <table>
                     <thead>
@@ -128,7 +232,7 @@ impl Game {
                         <tr>
                             <td>
                                 <p>{spectator_names}</p>
-                                if !view.is_started && view.this_player.group != Group::Spectators {
+                                if view.this_player.group != Group::Spectators {
                                     <p>
                                         <button onclick={join_spectators}>{"join spectators"}</button>
                                     </p>
@@ -137,14 +241,14 @@ impl Game {
                             <td>
                                 <p><b>{"guessers"}</b></p>
                                 <p>{blue_guessers_names}</p>
-                                if !view.is_started && view.this_player.group != Group::BlueGuessers {
+                                if view.this_player.group != Group::BlueGuessers {
                                     <p>
                                         <button onclick={join_blue_guessers}>{"join blue guessers"}</button>
                                     </p>
                                 }
                                 <p><b>{"spy masters"}</b></p>
                                 <p>{blue_spy_masters_names}</p>
-                                if !view.is_started && view.this_player.group != Group::BlueSpyMasters {
+                                if view.this_player.group != Group::BlueSpyMasters {
                                     <p>
                                         <button onclick={join_blue_spy_masters}>{"join blue spy masters"}</button>
                                     </p>
@@ -153,14 +257,14 @@ impl Game {
                             <td>
                                 <p><b>{"guessers"}</b></p>
                                 <p>{red_guessers_names}</p>
-                                if !view.is_started && view.this_player.group != Group::RedGuessers {
+                                if view.this_player.group != Group::RedGuessers {
                                     <p>
                                         <button onclick={join_red_guessers}>{"join red guessers"}</button>
                                     </p>
                                 }
                                 <p><b>{"spy masters"}</b></p>
                                 <p>{red_spy_masters_names}</p>
-                                if !view.is_started && view.this_player.group != Group::RedSpyMasters {
+                                if view.this_player.group != Group::RedSpyMasters {
                                     <p>
                                         <button onclick={join_red_spy_masters}>{"join red spy masters"}</button>
                                     </p>
@@ -169,11 +273,87 @@ impl Game {
                         </tr>
                     </tbody>
                 </table>
+                if view.this_player.is_host && !other_players.is_empty() {
+                    <table>
+                        <tbody>
+                            {
+                                for other_players.into_iter().map(|(public_id, player)| {
+                                    let transfer_host = transfer_host.clone();
+                                    let kick_player = kick_player.clone();
+                                    let transfer_id = public_id.clone();
+                                    let kick_id = public_id.clone();
+                                    html! {
+                                        <tr key={public_id.clone()}>
+                                            <td>{&player.name}</td>
+                                            <td><button onclick={move |_| transfer_host.emit(transfer_id.clone())}>{"make host"}</button></td>
+                                            <td><button onclick={move |_| kick_player.emit(kick_id.clone())}>{"kick"}</button></td>
+                                        </tr>
+                                    }
+                                })
+                            }
+                        </tbody>
+                    </table>
+                }
+                if has_pending_swap {
+                    <p>{"waiting for the host to approve your move"}</p>
+                }
+                if view.this_player.is_host && !pending_swaps.is_empty() {
+                    <table>
+                        <tbody>
+                            {
+                                for pending_swaps.into_iter().map(|(public_id, name, new_group)| {
+                                    let approve_swap = approve_swap.clone();
+                                    let deny_swap = deny_swap.clone();
+                                    let approve_id = public_id.clone();
+                                    let deny_id = public_id.clone();
+                                    html! {
+                                        <tr key={public_id}>
+                                            <td>{format!("{} wants to move to {:?}", name, new_group)}</td>
+                                            <td><button onclick={move |_| approve_swap.emit(approve_id.clone())}>{"approve"}</button></td>
+                                            <td><button onclick={move |_| deny_swap.emit(deny_id.clone())}>{"deny"}</button></td>
+                                        </tr>
+                                    }
+                                })
+                            }
+                        </tbody>
+                    </table>
+                }
+                if let Some(seed) = view.seed {
+                    <p>{format!("board seed: {}", seed)}</p>
+                }
                 if !view.is_started && view.this_player.is_host && enough_players_to_start {
                     <p>
                         <button onclick={move |_| start_game.emit(())}>{"start game"}</button>
                     </p>
                 }
+                if let Some(result) = &view.result {
+                    <h2>{format!("{} team wins!", result.winner)}</h2>
+                    <p>{result.reason.to_string()}</p>
+                    if view.this_player.is_host {
+                        <p>
+                            <button onclick={move |_| rematch.emit(false)}>{"rematch"}</button>
+                            <button onclick={move |_| rematch_clone.emit(true)}>{"rematch with new spy masters"}</button>
+                        </p>
+                    }
+                }
+                if let Some(deadline) = view.deadline {
+                    <p>{format!("{} team has {}s left", view.team_turn, seconds_until(deadline, view.paused_at))}</p>
+                }
+                if view.paused_at.is_some() {
+                    <p>{"game paused"}</p>
+                }
+                if is_in_progress && view.this_player.is_host {
+                    <p>
+                        if view.paused_at.is_some() {
+                            <button onclick={resume}>{"resume"}</button>
+                        } else {
+                            <button onclick={pause}>{"pause"}</button>
+                        }
+                    </p>
+                }
+                if view.series.blue + view.series.red > 0 {
+                    <p>{format!("series: blue {} - {} red", view.series.blue, view.series.red)}</p>
+                }
                 if view.is_started {
                     <table>
                         <tbody>
@@ -191,13 +371,7 @@ impl Game {
                                                                 {tile.word.clone() + " " + colour.to_string().as_str()}
                                                             } else {
                                                                 {&tile.word}
-                                                                if tile.colour.is_none() &&
-                                                                    view.next_action == Action::Guess &&
-                                                                    match view.team_turn {
-                                                                        TeamColour::Red => view.this_player.group == Group::RedGuessers,
-                                                                        TeamColour::Blue => view.this_player.group == Group::BlueGuessers,
-                                                                    }
-                                                                {
+                                                                if tile.colour.is_none() && is_playing && is_current_guesser {
                                                                     <button onclick={move |_| guess.clone().emit(index.try_into().unwrap())}>{"guess"}</button>
                                                                 }
                                                             }
@@ -234,7 +408,7 @@ impl Game {
                             </tbody>
                         </table>
                     }
-                    if view.next_action == Action::Clue &&
+                    if is_playing && view.next_action == Action::Clue &&
                         match view.team_turn {
                             TeamColour::Red => view.this_player.group == Group::RedSpyMasters,
                             TeamColour::Blue => view.this_player.group == Group::BlueSpyMasters,
@@ -244,19 +418,35 @@ impl Game {
                             <input type={"text"} oninput={set_clue_word} value={self.clue_input.word.clone()}/>
                         </label>
                         <label>{"count"}
-                            <input type={"number"} oninput={set_clue_count} min={"1"} max={"9"} value={self.clue_input.count.map(|count| count.to_string())}/>
+                            <input type={"number"} oninput={set_clue_count} min={"1"} max={"9"} value={match self.clue_input.count {
+                                Some(ClueCount::Number(count)) => count.to_string(),
+                                _ => "".to_string(),
+                            }}/>
                         </label>
+                        <button onclick={move |_| set_zero_clue_count.emit(ClueCount::Zero)}>{"0"}</button>
+                        <button onclick={move |_| set_special_clue_count.emit(ClueCount::Unlimited)}>{"∞"}</button>
                         if let Some(clue_count) = self.clue_input.count {
-                            if self.clue_input.word.trim().len() > 0 && clue_count >= 1 && clue_count <= 9 {
-                                <button onclick={provide_clue}>{"submit clue"}</button>
-                            }
+                            <span>{format!("count: {}", clue_count)}</span>
+                        }
+                        if !self.clue_input.word.trim().is_empty() && is_clue_count_valid {
+                            <button onclick={provide_clue}>{"submit clue"}</button>
                         }
                     }
                     if let Some(last_clue) = last_clue {
-                        if view.next_action == Action::Guess {
+                        if is_playing && view.next_action == Action::Guess {
                             {format!("CLUE: {} - {}", last_clue.word, last_clue.count)}
                         }
                     }
+                    if is_playing && is_current_guesser && has_guessed {
+                        <p>
+                            <button onclick={move |_| end_turn.emit(())}>{"end turn"}</button>
+                        </p>
+                    }
+                    if is_in_progress && is_in_team {
+                        <p>
+                            <button onclick={move |_| forfeit.emit(())}>{"forfeit"}</button>
+                        </p>
+                    }
                 }
             </div>
         }
@@ -267,8 +457,10 @@ impl Game {
 pub enum GameMsg {
     ReceiveMessage(ServerMessage),
     SendMessage(ClientMessage),
+    SendRequest(EventRequest),
     PlayerJoined(bool),
     SetClueInput(ClueInput),
+    Tick,
 }
 
 #[derive(PartialEq, Properties)]
@@ -299,13 +491,22 @@ impl Component for Game {
                 word: "".to_string(),
                 count: None,
             },
+            error: None,
+            next_request_id: 0,
+            pending_requests: HashMap::new(),
+            is_expired: false,
+            is_kicked: false,
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         html! {
-            if self.view.is_some() {
-                {self.render_game_view(self.view.as_ref().unwrap(), ctx)}
+            if self.is_expired {
+                <h1>{"this game has expired"}</h1>
+            } else if self.is_kicked {
+                <h1>{"you have been kicked from this game"}</h1>
+            } else if let Some(view) = self.view.as_ref() {
+                {self.render_game_view(view, ctx)}
             } else {
                 <h1>{"loading..."}</h1>
             }
@@ -317,7 +518,26 @@ impl Component for Game {
         match msg {
             GameMsg::ReceiveMessage(message) => match message {
                 ServerMessage::StateUpdate(view) => {
-                    self.view = Some(view);
+                    self.view = Some(*view);
+                    true
+                }
+                ServerMessage::Ack { request_id } => {
+                    self.pending_requests.remove(&request_id);
+                    true
+                }
+                ServerMessage::Error(error) => {
+                    if let Some(request_id) = error.request_id {
+                        self.pending_requests.remove(&request_id);
+                    }
+                    self.error = Some(error);
+                    true
+                }
+                ServerMessage::GameExpired => {
+                    self.is_expired = true;
+                    true
+                }
+                ServerMessage::Kicked => {
+                    self.is_kicked = true;
                     true
                 }
             },
@@ -329,6 +549,27 @@ impl Component for Game {
                 }
                 false
             }
+            GameMsg::SendRequest(request) => {
+                if self
+                    .pending_requests
+                    .values()
+                    .any(|pending| *pending == request)
+                {
+                    // Drop duplicate submissions, e.g. from double-clicks.
+                    return false;
+                }
+                let request_id = self.next_request_id;
+                self.next_request_id += 1;
+                self.pending_requests.insert(request_id, request.clone());
+                // A new request supersedes the error from any previous one.
+                self.error = None;
+                ctx.link()
+                    .send_message(GameMsg::SendMessage(ClientMessage::EventRequest {
+                        request_id: Some(request_id),
+                        request,
+                    }));
+                true
+            }
             GameMsg::PlayerJoined(player_joined) => {
                 if player_joined {
                     self.websocket = Some(events(ctx.props().game_id.clone(), ctx.link()));
@@ -341,6 +582,17 @@ impl Component for Game {
                 self.clue_input = clue_input;
                 true
             }
+            GameMsg::Tick => self
+                .view
+                .as_ref()
+                .is_some_and(|view| view.deadline.is_some()),
         }
     }
 }
+
+/// Whole seconds left before the given deadline, in milliseconds since the Unix epoch. The clock
+/// stands still while the game is paused.
+fn seconds_until(deadline: u64, paused_at: Option<u64>) -> u64 {
+    let now = paused_at.unwrap_or_else(|| js_sys::Date::now() as u64);
+    deadline.saturating_sub(now).div_ceil(1000)
+}
@@ -45,7 +45,7 @@ impl Component for Menu {
             html! {
                 <>
                     <SimpleInput label_name={"name:"} value={self.player_name.clone()} set_value={set_player_name}/>
-                    if self.player_name.trim().len() > 0 {
+                    if !self.player_name.trim().is_empty() {
                         <button {onclick}>{"join game"}</button>
                     }
                 </>
@@ -69,7 +69,7 @@ impl Component for Menu {
                         .push_state_with_url(
                             &wasm_bindgen::JsValue::null(),
                             "",
-                            Some(&new_url.as_str()),
+                            Some(new_url.as_str()),
                         )
                         .unwrap();
                     MenuMsg::SetJoined
@@ -78,7 +78,7 @@ impl Component for Menu {
             html! {
                 <>
                     <SimpleInput label_name={"name:"} value={self.player_name.clone()} set_value={set_player_name}/>
-                    if self.player_name.trim().len() > 0 {
+                    if !self.player_name.trim().is_empty() {
                         <button {onclick}>{"new game"}</button>
                     }
                 </>